@@ -0,0 +1,42 @@
+use std::time::{Duration, Instant};
+use scriptx::lexer::lexer::Lexer;
+
+/// Builds a script of roughly `bytes` bytes out of `let` statements, mixing in
+/// non-ASCII identifiers so the byte/char distinction is exercised.
+fn generate_script(bytes: usize) -> String {
+    let mut script = String::with_capacity(bytes + 64);
+    let mut i = 0;
+    while script.len() < bytes {
+        script.push_str(&format!("let größe = (größe + {}) * 3 - café / 2.5;\n", i));
+        i += 1;
+    }
+    script
+}
+
+fn lex(source: &str) -> usize {
+    Lexer::new(source).count()
+}
+
+fn time(source: &str) -> (usize, Duration) {
+    let mut best = Duration::MAX;
+    let mut tokens = 0;
+
+    for _ in 0..3 {
+        let start = Instant::now();
+        tokens = lex(source);
+        best = best.min(start.elapsed());
+    }
+
+    (tokens, best)
+}
+
+fn main() {
+    println!("{:>10} {:>10} {:>12} {:>10}", "bytes", "tokens", "time", "MB/s");
+
+    for megabytes in [1, 2, 4, 8] {
+        let source = generate_script(megabytes * 1024 * 1024);
+        let (tokens, elapsed) = time(&source);
+        let throughput = source.len() as f64 / elapsed.as_secs_f64() / (1024.0 * 1024.0);
+        println!("{:>10} {:>10} {:>12?} {:>10.1}", source.len(), tokens, elapsed, throughput);
+    }
+}
@@ -0,0 +1,87 @@
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use scriptx::compiler::compiler::compile;
+use scriptx::interpreter::interpreter::Interpreter;
+use scriptx::lexer::lexer::Lexer;
+use scriptx::parser::parser::{parse, Parser, Program};
+use scriptx::resolver::resolver::resolve;
+use scriptx::vm::vm::Vm;
+
+/// Arithmetic-heavy scripts, each leaving its answer in `result`.
+const SCRIPTS: &[(&str, &str)] = &[
+    ("sum loop", "
+        let mut result = 0;
+        let mut i = 0;
+        while i < 200000 {
+            result = (result + i * 3 - i / 2) % 1000003;
+            i += 1;
+        }
+    "),
+    ("nested for", "
+        let mut result = 0;
+        for i in 0..400 {
+            for j in 0..400 {
+                result = (result + i * j + 7) % 65521;
+            }
+        }
+    "),
+    ("fib", "
+        let fib = fn(n) { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } };
+        let result = fib(20);
+    "),
+    ("locals in a function", "
+        let work = fn(n) {
+            let mut total = 0.0;
+            let mut x = 1.5;
+            for i in 0..n {
+                x = x * 1.0001 + 0.5;
+                total += x / (i + 1);
+            }
+            total
+        };
+        let result = work(100000);
+    "),
+];
+
+fn program(source: &str) -> Program {
+    let mut program = parse(&mut Parser::new(Lexer::new(source))).expect("benchmark scripts parse");
+    assert!(resolve(&mut program).is_empty(), "benchmark scripts resolve cleanly");
+    program
+}
+
+fn best_of_three(mut run: impl FnMut()) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..3 {
+        let start = Instant::now();
+        run();
+        best = best.min(start.elapsed());
+    }
+    best
+}
+
+fn main() {
+    println!("{:>22} {:>12} {:>12} {:>8}", "script", "tree-walker", "vm", "speedup");
+
+    for (name, source) in SCRIPTS {
+        let program = program(source);
+        let bytecode = Rc::new(compile(&program));
+
+        let mut walked = None;
+        let tree_walker = best_of_three(|| {
+            let mut interpreter = Interpreter::new();
+            interpreter.run(&program).expect("benchmark scripts run");
+            walked = interpreter.get("result");
+        });
+
+        let mut executed = None;
+        let vm = best_of_three(|| {
+            let mut vm = Vm::new();
+            vm.run(&bytecode).expect("benchmark scripts run");
+            executed = vm.get("result");
+        });
+
+        assert_eq!(walked, executed, "both engines agree on {}", name);
+        let speedup = tree_walker.as_secs_f64() / vm.as_secs_f64();
+        println!("{:>22} {:>12?} {:>12?} {:>7.1}x", name, tree_walker, vm, speedup);
+    }
+}
@@ -0,0 +1,148 @@
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use crate::builtins::{lists, maps, strings};
+use crate::datatypes::datatypes::{DataType, NumberType};
+use crate::datatypes::map::Map;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::error::RuntimeErrorKind;
+
+pub type BuiltinFunction = fn(&[DataType]) -> Result<DataType, RuntimeErrorKind>;
+
+/// A function implemented in Rust and made available to every script.
+#[derive(Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: usize,
+    pub function: BuiltinFunction,
+}
+
+impl Builtin {
+    pub fn call(&self, arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+        if arguments.len() != self.arity {
+            return Err(RuntimeErrorKind::ArgumentCountMismatch {
+                function: Rc::from(self.name),
+                expected: self.arity,
+                found: arguments.len()
+            })
+        }
+        (self.function)(arguments)
+    }
+}
+
+impl Debug for Builtin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<builtin {}>", self.name)
+    }
+}
+
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+pub const BUILTINS: &[Builtin] = &[
+    Builtin { name: "len", arity: 1, function: len },
+    Builtin { name: "slice", arity: 3, function: strings::slice },
+    Builtin { name: "split", arity: 2, function: strings::split },
+    Builtin { name: "trim", arity: 1, function: strings::trim },
+    Builtin { name: "contains", arity: 2, function: contains },
+    Builtin { name: "replace", arity: 3, function: strings::replace },
+    Builtin { name: "upper", arity: 1, function: strings::upper },
+    Builtin { name: "lower", arity: 1, function: strings::lower },
+    Builtin { name: "push", arity: 2, function: lists::push },
+    Builtin { name: "pop", arity: 1, function: lists::pop },
+    Builtin { name: "insert", arity: 3, function: insert },
+    Builtin { name: "remove", arity: 2, function: remove },
+    Builtin { name: "sort", arity: 1, function: lists::sort },
+    Builtin { name: "reverse", arity: 1, function: lists::reverse },
+    Builtin { name: "keys", arity: 1, function: maps::keys },
+    Builtin { name: "values", arity: 1, function: maps::values },
+    Builtin { name: "entries", arity: 1, function: maps::entries },
+];
+
+/// Binds every builtin by name in the innermost scope of `env`.
+pub fn define_builtins(env: &mut Environment) {
+    for builtin in BUILTINS {
+        env.define(Rc::from(builtin.name), DataType::Builtin(*builtin));
+    }
+}
+
+pub fn invalid_argument(function: &'static str, message: String) -> RuntimeErrorKind {
+    RuntimeErrorKind::InvalidArgument { function, message }
+}
+
+pub fn string_argument<'a>(function: &'static str, arguments: &'a [DataType], index: usize) -> Result<&'a Rc<str>, RuntimeErrorKind> {
+    match &arguments[index] {
+        DataType::String(s) => Ok(s),
+        other => Err(invalid_argument(function, format!("expected a string for argument {}, found {}", index + 1, other.type_name())))
+    }
+}
+
+pub fn list_argument<'a>(function: &'static str, arguments: &'a [DataType], index: usize) -> Result<&'a Rc<RefCell<Vec<DataType>>>, RuntimeErrorKind> {
+    match &arguments[index] {
+        DataType::List(items) => Ok(items),
+        other => Err(invalid_argument(function, format!("expected a list for argument {}, found {}", index + 1, other.type_name())))
+    }
+}
+
+pub fn map_argument<'a>(function: &'static str, arguments: &'a [DataType], index: usize) -> Result<&'a Rc<RefCell<Map>>, RuntimeErrorKind> {
+    match &arguments[index] {
+        DataType::Map(map) => Ok(map),
+        other => Err(invalid_argument(function, format!("expected a map for argument {}, found {}", index + 1, other.type_name())))
+    }
+}
+
+pub fn integer_argument(function: &'static str, arguments: &[DataType], index: usize) -> Result<i64, RuntimeErrorKind> {
+    match &arguments[index] {
+        DataType::Number(NumberType::Integer(i)) => Ok(*i),
+        other => Err(invalid_argument(function, format!("expected an integer for argument {}, found {}", index + 1, other.type_name())))
+    }
+}
+
+pub fn integer_result(function: &'static str, value: usize) -> Result<DataType, RuntimeErrorKind> {
+    match i64::try_from(value) {
+        Ok(v) => Ok(DataType::Number(NumberType::Integer(v))),
+        Err(_) => Err(RuntimeErrorKind::Overflow { operation: function })
+    }
+}
+
+fn len(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    match &arguments[0] {
+        DataType::String(s) => integer_result("len", s.chars().count()),
+        DataType::List(items) => integer_result("len", items.borrow().len()),
+        DataType::Map(map) => integer_result("len", map.borrow().len()),
+        other => Err(invalid_argument("len", format!("expected a string, list or map, found {}", other.type_name())))
+    }
+}
+
+/// `contains(s, needle)` for a substring of a string, `contains(xs, item)`
+/// for an item of a list, or `contains(m, key)` for a key of a map.
+fn contains(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    match &arguments[0] {
+        DataType::String(_) => strings::contains(arguments),
+        DataType::List(items) => Ok(DataType::Boolean(lists::contains(&items.borrow(), &arguments[1]))),
+        DataType::Map(map) => {
+            let key = string_argument("contains", arguments, 1)?;
+            Ok(DataType::Boolean(map.borrow().contains_key(key)))
+        }
+        other => Err(invalid_argument("contains", format!("expected a string, list or map, found {}", other.type_name())))
+    }
+}
+
+/// `insert(xs, index, item)` for a list or `insert(m, key, value)` for a map.
+fn insert(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    match &arguments[0] {
+        DataType::Map(_) => maps::insert(arguments),
+        _ => lists::insert(arguments)
+    }
+}
+
+/// `remove(xs, index)` for a list or `remove(m, key)` for a map.
+fn remove(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    match &arguments[0] {
+        DataType::Map(_) => maps::remove(arguments),
+        _ => lists::remove(arguments)
+    }
+}
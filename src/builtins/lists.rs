@@ -0,0 +1,99 @@
+use std::cmp::Ordering;
+use crate::builtins::builtins::{integer_argument, invalid_argument, list_argument};
+use crate::datatypes::datatypes::{resolve_bound, resolve_index, DataType, NumberType};
+use crate::interpreter::error::RuntimeErrorKind;
+use crate::operators::operators::{ComparisonOperatorTrait, EqualityOperatorTrait};
+
+/// `push(xs, item)`: appends `item` to the end of `xs`.
+pub fn push(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let items = list_argument("push", arguments, 0)?;
+    items.borrow_mut().push(arguments[1].clone());
+    Ok(DataType::Nil)
+}
+
+/// `pop(xs)`: removes the last item of `xs` and returns it.
+pub fn pop(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let items = list_argument("pop", arguments, 0)?;
+    let item = items.borrow_mut().pop();
+    item.ok_or_else(|| invalid_argument("pop", String::from("the list is empty")))
+}
+
+/// `insert(xs, index, item)`: puts `item` at `index`, moving the items from
+/// there on up by one. `index` may be `len(xs)` to append.
+pub fn insert(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let items = list_argument("insert", arguments, 0)?;
+    let index = integer_argument("insert", arguments, 1)?;
+    let mut items = items.borrow_mut();
+    let position = resolve_bound(index, items.len())?;
+    items.insert(position, arguments[2].clone());
+    Ok(DataType::Nil)
+}
+
+/// `remove(xs, index)`: takes the item at `index` out of `xs` and returns it.
+pub fn remove(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let items = list_argument("remove", arguments, 0)?;
+    let index = integer_argument("remove", arguments, 1)?;
+    let mut items = items.borrow_mut();
+    let position = resolve_index(index, items.len())?;
+    Ok(items.remove(position))
+}
+
+/// `sort(xs)`: sorts `xs` in place, numbers numerically and strings
+/// lexicographically. A list mixing the two, or holding anything else that
+/// has no order, is an error and is left as it was.
+pub fn sort(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let items = list_argument("sort", arguments, 0)?;
+    let mut items = items.borrow_mut();
+
+    // Comparing every neighbouring pair first means the items are known to be
+    // all numbers or all strings before anything is moved.
+    for pair in items.windows(2) {
+        pair[0].compare(&pair[1])?;
+    }
+
+    items.sort_by(|a, b| match (a, b) {
+        (DataType::String(a), DataType::String(b)) => a.cmp(b),
+        (DataType::Number(NumberType::Integer(a)), DataType::Number(NumberType::Integer(b))) => a.cmp(b),
+        (DataType::Number(NumberType::Integer(a)), DataType::Number(NumberType::Float(b))) => integer_to_float(*a, *b),
+        (DataType::Number(NumberType::Float(a)), DataType::Number(NumberType::Integer(b))) => integer_to_float(*b, *a).reverse(),
+        (DataType::Number(NumberType::Float(a)), DataType::Number(NumberType::Float(b))) => float_to_float(*a, *b),
+        _ => Ordering::Equal
+    });
+    Ok(DataType::Nil)
+}
+
+/// Orders an integer against a float exactly, as integers past 2^53 can't
+/// all be told apart once cast to floats. NaN sorts after every number.
+fn integer_to_float(integer: i64, float: f64) -> Ordering {
+    if float.is_nan() || float >= 9223372036854775808.0 {
+        return Ordering::Less;
+    }
+    if float < -9223372036854775808.0 {
+        return Ordering::Greater;
+    }
+    let whole = float.trunc();
+    integer.cmp(&(whole as i64)).then_with(|| 0.0.partial_cmp(&(float - whole)).expect("the fraction is a number"))
+}
+
+/// Equal floats compare equal, `0.0` and `-0.0` included, so that both match
+/// the integer `0`. NaN sorts after every number.
+fn float_to_float(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => a.partial_cmp(&b).expect("neither is NaN"),
+        (nan_a, nan_b) => nan_a.cmp(&nan_b),
+    }
+}
+
+/// `reverse(xs)`: reverses the order of the items of `xs` in place.
+pub fn reverse(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let items = list_argument("reverse", arguments, 0)?;
+    items.borrow_mut().reverse();
+    Ok(DataType::Nil)
+}
+
+/// Whether `xs` has an item equal to `item`, with the same notion of equality
+/// as `==`.
+pub fn contains(items: &[DataType], item: &DataType) -> bool {
+    items.iter().any(|candidate| candidate.equals(item))
+}
+
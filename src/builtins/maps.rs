@@ -0,0 +1,45 @@
+use std::rc::Rc;
+use crate::builtins::builtins::{map_argument, string_argument};
+use crate::datatypes::datatypes::DataType;
+use crate::interpreter::error::RuntimeErrorKind;
+
+/// `insert(m, key, value)`: sets `key` to `value`, adding the key at the end
+/// if `m` didn't have it yet.
+pub fn insert(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let map = map_argument("insert", arguments, 0)?;
+    let key = string_argument("insert", arguments, 1)?;
+    map.borrow_mut().insert(Rc::clone(key), arguments[2].clone());
+    Ok(DataType::Nil)
+}
+
+/// `remove(m, key)`: takes `key` out of `m` and returns its value.
+pub fn remove(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let map = map_argument("remove", arguments, 0)?;
+    let key = string_argument("remove", arguments, 1)?;
+    let value = map.borrow_mut().remove(key);
+    value.ok_or_else(|| RuntimeErrorKind::MissingKey(Rc::clone(key)))
+}
+
+/// `keys(m)`: the keys of `m` as a list, in insertion order.
+pub fn keys(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let map = map_argument("keys", arguments, 0)?;
+    let keys = map.borrow().keys().map(|key| DataType::String(Rc::clone(key))).collect();
+    Ok(DataType::new_list(keys))
+}
+
+/// `values(m)`: the values of `m` as a list, in insertion order.
+pub fn values(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let map = map_argument("values", arguments, 0)?;
+    let values = map.borrow().values().cloned().collect();
+    Ok(DataType::new_list(values))
+}
+
+/// `entries(m)`: a `[key, value]` list for every entry of `m`, in insertion
+/// order.
+pub fn entries(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let map = map_argument("entries", arguments, 0)?;
+    let entries = map.borrow().entries()
+        .map(|(key, value)| DataType::new_list(vec![DataType::String(Rc::clone(key)), value.clone()]))
+        .collect();
+    Ok(DataType::new_list(entries))
+}
@@ -0,0 +1,5 @@
+#[allow(clippy::module_inception)]
+pub mod builtins;
+pub mod strings;
+pub mod lists;
+pub mod maps;
\ No newline at end of file
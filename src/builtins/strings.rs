@@ -0,0 +1,73 @@
+use std::rc::Rc;
+use crate::builtins::builtins::{integer_argument, invalid_argument, string_argument};
+use crate::datatypes::datatypes::DataType;
+use crate::interpreter::error::RuntimeErrorKind;
+
+/// `slice(s, start, end)`: the characters of `s` from `start` up to but not
+/// including `end`. Indices count characters, not bytes.
+pub fn slice(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let s = string_argument("slice", arguments, 0)?;
+    let start = integer_argument("slice", arguments, 1)?;
+    let end = integer_argument("slice", arguments, 2)?;
+    let length = s.chars().count();
+
+    for index in [start, end] {
+        if index < 0 || index as usize > length {
+            return Err(RuntimeErrorKind::IndexOutOfBounds { index, length })
+        }
+    }
+
+    if start > end {
+        return Err(invalid_argument("slice", format!("start {} is after end {}", start, end)))
+    }
+
+    let sliced: String = s.chars().skip(start as usize).take((end - start) as usize).collect();
+    Ok(DataType::String(Rc::from(sliced)))
+}
+
+pub fn split(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let s = string_argument("split", arguments, 0)?;
+    let separator = string_argument("split", arguments, 1)?;
+
+    if separator.is_empty() {
+        return Err(invalid_argument("split", String::from("the separator must not be empty")))
+    }
+
+    let parts = s.split(separator.as_ref())
+        .map(|part| DataType::String(Rc::from(part)))
+        .collect();
+    Ok(DataType::new_list(parts))
+}
+
+pub fn trim(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let s = string_argument("trim", arguments, 0)?;
+    Ok(DataType::String(Rc::from(s.trim())))
+}
+
+pub fn contains(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let s = string_argument("contains", arguments, 0)?;
+    let needle = string_argument("contains", arguments, 1)?;
+    Ok(DataType::Boolean(s.contains(needle.as_ref())))
+}
+
+pub fn replace(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let s = string_argument("replace", arguments, 0)?;
+    let from = string_argument("replace", arguments, 1)?;
+    let to = string_argument("replace", arguments, 2)?;
+
+    if from.is_empty() {
+        return Err(invalid_argument("replace", String::from("the pattern must not be empty")))
+    }
+
+    Ok(DataType::String(Rc::from(s.replace(from.as_ref(), to))))
+}
+
+pub fn upper(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let s = string_argument("upper", arguments, 0)?;
+    Ok(DataType::String(Rc::from(s.to_uppercase())))
+}
+
+pub fn lower(arguments: &[DataType]) -> Result<DataType, RuntimeErrorKind> {
+    let s = string_argument("lower", arguments, 0)?;
+    Ok(DataType::String(Rc::from(s.to_lowercase())))
+}
@@ -0,0 +1,343 @@
+use std::rc::Rc;
+use crate::compiler::bytecode::{Bytecode, Capture, Condition, Instruction, Prototype};
+use crate::compiler::error::LoadError;
+use crate::compiler::verify::verify;
+use crate::datatypes::datatypes::{DataType, NumberType};
+use crate::lexer::span::Span;
+use crate::operators::operators::{BinaryOperator, UnaryOperator};
+
+/// The first bytes of every compiled file.
+pub const MAGIC: &[u8; 4] = b"SXBC";
+
+/// Bumped whenever the layout below or the meaning of an instruction changes,
+/// so older files are turned away instead of misread.
+pub const VERSION: u16 = 3;
+
+const HEADER_LENGTH: usize = MAGIC.len() + 2 + 8;
+
+// The numbering of operators and conditions in the file is their position here.
+const BINARY_OPERATORS: [BinaryOperator; 13] = [
+    BinaryOperator::Add, BinaryOperator::Subtract, BinaryOperator::Multiply, BinaryOperator::Divide,
+    BinaryOperator::Remainder, BinaryOperator::Or, BinaryOperator::And, BinaryOperator::Equal,
+    BinaryOperator::NotEqual, BinaryOperator::LessThan, BinaryOperator::GreaterThan,
+    BinaryOperator::LessThanOrEqual, BinaryOperator::GreaterThanOrEqual,
+];
+const UNARY_OPERATORS: [UnaryOperator; 3] = [UnaryOperator::Not, UnaryOperator::Negate, UnaryOperator::Plus];
+const CONDITIONS: [Condition; 4] = [Condition::If, Condition::While, Condition::Or, Condition::And];
+
+/// The layout, with every number little-endian:
+///
+/// - header: `MAGIC`, the `VERSION` as a `u16`, and a 64-bit FNV-1a checksum
+///   of everything after the header.
+/// - constant pool: a `u32` count, then a tag byte and the value of each constant.
+/// - function table: a `u32` count, then the name, arity, slot counts,
+///   captures and code of each function.
+/// - debug span table: for each function in turn, a `u32` count and the
+///   span of each of its instructions.
+///
+/// Strings are a `u32` byte length followed by UTF-8.
+impl Bytecode {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut body = Writer::default();
+
+        body.u32(self.constants.len() as u32);
+        for constant in &self.constants {
+            match constant {
+                DataType::Number(NumberType::Integer(v)) => { body.u8(0); body.u64(*v as u64); }
+                DataType::Number(NumberType::Float(v)) => { body.u8(1); body.u64(v.to_bits()); }
+                DataType::Boolean(v) => { body.u8(2); body.bool(*v); }
+                DataType::String(v) => { body.u8(3); body.str(v); }
+                other => unreachable!("the compiler makes no {} constants", other.type_name()),
+            }
+        }
+
+        body.u32(self.functions.len() as u32);
+        for function in &self.functions {
+            match &function.name {
+                Some(name) => { body.bool(true); body.str(name); }
+                None => body.bool(false),
+            }
+            body.u32(function.arity);
+            body.u32(function.slots);
+            body.u32(function.iterators);
+            body.u32(function.captures.len() as u32);
+            for capture in &function.captures {
+                match capture {
+                    Capture::Local(slot) => { body.u8(0); body.u32(*slot); }
+                    Capture::Upvalue(index) => { body.u8(1); body.u32(*index); }
+                }
+            }
+            body.u32(function.code.len() as u32);
+            for instruction in &function.code {
+                body.instruction(*instruction);
+            }
+        }
+
+        for function in &self.functions {
+            body.u32(function.spans.len() as u32);
+            for span in &function.spans {
+                for part in [span.start, span.end, span.line, span.column] {
+                    body.u32(part as u32);
+                }
+            }
+        }
+
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + body.bytes.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&checksum(&body.bytes).to_le_bytes());
+        bytes.extend_from_slice(&body.bytes);
+        bytes
+    }
+
+    /// Decodes a file written by `to_bytes`, checking that it is intact and
+    /// that the code in it is safe for the `vm` to run.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Bytecode, LoadError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(LoadError::NotBytecode);
+        }
+        let mut header = Reader { bytes, position: MAGIC.len() };
+        let version = header.u16()?;
+        if version != VERSION {
+            return Err(LoadError::UnsupportedVersion { found: version, supported: VERSION });
+        }
+        let expected = header.u64()?;
+        if checksum(&bytes[HEADER_LENGTH..]) != expected {
+            return Err(LoadError::ChecksumMismatch);
+        }
+
+        let mut body = Reader { bytes, position: HEADER_LENGTH };
+
+        let mut constants = Vec::new();
+        for _ in 0..body.u32()? {
+            constants.push(match body.u8()? {
+                0 => DataType::Number(NumberType::Integer(body.u64()? as i64)),
+                1 => DataType::Number(NumberType::Float(f64::from_bits(body.u64()?))),
+                2 => DataType::Boolean(body.bool()?),
+                3 => DataType::String(body.str()?),
+                tag => return Err(LoadError::Malformed(format!("unknown constant tag {}", tag))),
+            });
+        }
+
+        let mut functions = Vec::new();
+        for _ in 0..body.u32()? {
+            let name = if body.bool()? { Some(body.str()?) } else { None };
+            let arity = body.u32()?;
+            let slots = body.u32()?;
+            let iterators = body.u32()?;
+            let mut captures = Vec::new();
+            for _ in 0..body.u32()? {
+                captures.push(match body.u8()? {
+                    0 => Capture::Local(body.u32()?),
+                    1 => Capture::Upvalue(body.u32()?),
+                    tag => return Err(LoadError::Malformed(format!("unknown capture tag {}", tag))),
+                });
+            }
+            let mut code = Vec::new();
+            for _ in 0..body.u32()? {
+                code.push(body.instruction()?);
+            }
+            functions.push(Prototype { name, arity, slots, iterators, captures, code, spans: Vec::new() });
+        }
+
+        for function in &mut functions {
+            for _ in 0..body.u32()? {
+                let (start, end, line, column) = (body.u32()?, body.u32()?, body.u32()?, body.u32()?);
+                function.spans.push(Span::new(start as usize, end as usize, line as usize, column as usize));
+            }
+        }
+
+        if body.position != bytes.len() {
+            return Err(LoadError::Malformed(format!("{} unexpected bytes at the end", bytes.len() - body.position)));
+        }
+
+        let bytecode = Bytecode { constants, functions };
+        verify(&bytecode)?;
+        Ok(bytecode)
+    }
+}
+
+/// 64-bit FNV-1a.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn label(&mut self, label: Option<u32>) {
+        self.bool(label.is_some());
+        if let Some(label) = label {
+            self.u32(label);
+        }
+    }
+
+    fn instruction(&mut self, instruction: Instruction) {
+        match instruction {
+            Instruction::Constant(index) => { self.u8(0); self.u32(index); }
+            Instruction::Nil => self.u8(1),
+            Instruction::Pop => self.u8(2),
+            Instruction::PopN(count) => { self.u8(3); self.u32(count); }
+            Instruction::Pick(depth) => { self.u8(4); self.u32(depth); }
+            Instruction::Swap => self.u8(5),
+            Instruction::GetLocal(slot) => { self.u8(6); self.u32(slot); }
+            Instruction::SetLocal(slot) => { self.u8(7); self.u32(slot); }
+            Instruction::DeclareLocal(slot) => { self.u8(8); self.u32(slot); }
+            Instruction::GetUpvalue(index) => { self.u8(9); self.u32(index); }
+            Instruction::SetUpvalue(index) => { self.u8(10); self.u32(index); }
+            Instruction::GetGlobal(name) => { self.u8(11); self.u32(name); }
+            Instruction::SetGlobal(name) => { self.u8(12); self.u32(name); }
+            Instruction::DefineGlobal { name, mutable } => { self.u8(13); self.u32(name); self.bool(mutable); }
+            Instruction::Binary(op) => { self.u8(14); self.u8(position(&BINARY_OPERATORS, op)); }
+            Instruction::Unary(op) => { self.u8(15); self.u8(position(&UNARY_OPERATORS, op)); }
+            Instruction::CheckBoolean(condition) => { self.u8(16); self.u8(position(&CONDITIONS, condition)); }
+            Instruction::Jump(target) => { self.u8(17); self.u32(target); }
+            Instruction::JumpIfFalse(target, condition) => {
+                self.u8(18);
+                self.u32(target);
+                self.u8(position(&CONDITIONS, condition));
+            }
+            Instruction::Or(target) => { self.u8(19); self.u32(target); }
+            Instruction::And(target) => { self.u8(20); self.u32(target); }
+            Instruction::Call(count) => { self.u8(21); self.u32(count); }
+            Instruction::Closure(function) => { self.u8(22); self.u32(function); }
+            Instruction::Return => self.u8(23),
+            Instruction::List(count) => { self.u8(24); self.u32(count); }
+            Instruction::Map => self.u8(25),
+            Instruction::Index => self.u8(26),
+            Instruction::SetIndex => self.u8(27),
+            Instruction::Slice { start, end } => { self.u8(28); self.bool(start); self.bool(end); }
+            Instruction::RangeIterator(iterator) => { self.u8(29); self.u32(iterator); }
+            Instruction::ValueIterator(iterator) => { self.u8(30); self.u32(iterator); }
+            Instruction::IterateNext { iterator, done } => { self.u8(31); self.u32(iterator); self.u32(done); }
+            Instruction::ImmutableAssignment(name) => { self.u8(32); self.u32(name); }
+            Instruction::ReturnOutsideFunction => self.u8(33),
+            Instruction::BreakOutsideLoop(label) => { self.u8(34); self.label(label); }
+            Instruction::ContinueOutsideLoop(label) => { self.u8(35); self.label(label); }
+            Instruction::MapEntry(key) => { self.u8(36); self.u32(key); }
+        }
+    }
+}
+
+fn position<T: PartialEq>(table: &[T], item: T) -> u8 {
+    table.iter().position(|entry| *entry == item).expect("every variant is in its table") as u8
+}
+
+fn entry<T: Copy>(table: &[T], code: u8, what: &str) -> Result<T, LoadError> {
+    table.get(code as usize).copied().ok_or_else(|| LoadError::Malformed(format!("unknown {} {}", what, code)))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> Result<&[u8], LoadError> {
+        let end = self.position.checked_add(count).filter(|&end| end <= self.bytes.len()).ok_or(LoadError::Truncated)?;
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, LoadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, LoadError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(LoadError::Malformed(format!("{} is not a boolean", other))),
+        }
+    }
+
+    fn u16(&mut self) -> Result<u16, LoadError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().expect("took two bytes")))
+    }
+
+    fn u32(&mut self) -> Result<u32, LoadError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().expect("took four bytes")))
+    }
+
+    fn u64(&mut self) -> Result<u64, LoadError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().expect("took eight bytes")))
+    }
+
+    fn str(&mut self) -> Result<Rc<str>, LoadError> {
+        let length = self.u32()? as usize;
+        let bytes = self.take(length)?;
+        std::str::from_utf8(bytes).map(Rc::from).map_err(|_| LoadError::Malformed(String::from("a string is not valid UTF-8")))
+    }
+
+    fn label(&mut self) -> Result<Option<u32>, LoadError> {
+        Ok(if self.bool()? { Some(self.u32()?) } else { None })
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, LoadError> {
+        Ok(match self.u8()? {
+            0 => Instruction::Constant(self.u32()?),
+            1 => Instruction::Nil,
+            2 => Instruction::Pop,
+            3 => Instruction::PopN(self.u32()?),
+            4 => Instruction::Pick(self.u32()?),
+            5 => Instruction::Swap,
+            6 => Instruction::GetLocal(self.u32()?),
+            7 => Instruction::SetLocal(self.u32()?),
+            8 => Instruction::DeclareLocal(self.u32()?),
+            9 => Instruction::GetUpvalue(self.u32()?),
+            10 => Instruction::SetUpvalue(self.u32()?),
+            11 => Instruction::GetGlobal(self.u32()?),
+            12 => Instruction::SetGlobal(self.u32()?),
+            13 => Instruction::DefineGlobal { name: self.u32()?, mutable: self.bool()? },
+            14 => Instruction::Binary(entry(&BINARY_OPERATORS, self.u8()?, "binary operator")?),
+            15 => Instruction::Unary(entry(&UNARY_OPERATORS, self.u8()?, "unary operator")?),
+            16 => Instruction::CheckBoolean(entry(&CONDITIONS, self.u8()?, "condition")?),
+            17 => Instruction::Jump(self.u32()?),
+            18 => Instruction::JumpIfFalse(self.u32()?, entry(&CONDITIONS, self.u8()?, "condition")?),
+            19 => Instruction::Or(self.u32()?),
+            20 => Instruction::And(self.u32()?),
+            21 => Instruction::Call(self.u32()?),
+            22 => Instruction::Closure(self.u32()?),
+            23 => Instruction::Return,
+            24 => Instruction::List(self.u32()?),
+            25 => Instruction::Map,
+            26 => Instruction::Index,
+            27 => Instruction::SetIndex,
+            28 => Instruction::Slice { start: self.bool()?, end: self.bool()? },
+            29 => Instruction::RangeIterator(self.u32()?),
+            30 => Instruction::ValueIterator(self.u32()?),
+            31 => Instruction::IterateNext { iterator: self.u32()?, done: self.u32()? },
+            32 => Instruction::ImmutableAssignment(self.u32()?),
+            33 => Instruction::ReturnOutsideFunction,
+            34 => Instruction::BreakOutsideLoop(self.label()?),
+            35 => Instruction::ContinueOutsideLoop(self.label()?),
+            36 => Instruction::MapEntry(self.u32()?),
+            opcode => return Err(LoadError::Malformed(format!("unknown opcode {}", opcode))),
+        })
+    }
+}
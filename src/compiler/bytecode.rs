@@ -0,0 +1,162 @@
+use std::rc::Rc;
+use crate::datatypes::datatypes::DataType;
+use crate::lexer::span::Span;
+use crate::operators::operators::{BinaryOperator, UnaryOperator};
+
+/// A single step of the virtual machine. Operands are read from and results
+/// pushed onto the operand stack; locals live in numbered slots of the
+/// running function instead. Jump targets are absolute instruction indices.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    /// Pushes a value from the constant pool.
+    Constant(u32),
+    Nil,
+    Pop,
+    /// Pops this many values at once, to leave a loop from the middle of an expression.
+    PopN(u32),
+    /// Pushes a copy of the value this far below the top; `Pick(0)` duplicates the top.
+    Pick(u32),
+    /// Swaps the two values on top.
+    Swap,
+
+    GetLocal(u32),
+    /// Pops into an existing local, writing through to closures that captured it.
+    SetLocal(u32),
+    /// Pops into a local that is being declared, replacing whatever the slot held.
+    DeclareLocal(u32),
+    GetUpvalue(u32),
+    SetUpvalue(u32),
+    /// Globals are found by name, given as a string constant.
+    GetGlobal(u32),
+    SetGlobal(u32),
+    DefineGlobal { name: u32, mutable: bool },
+
+    Binary(BinaryOperator),
+    Unary(UnaryOperator),
+    /// Fails unless the top value is a boolean.
+    CheckBoolean(Condition),
+    Jump(u32),
+    /// Pops a boolean and jumps if it is false.
+    JumpIfFalse(u32, Condition),
+    /// The left operand of `||`: pops it, and if it is true pushes it back and jumps.
+    Or(u32),
+    /// The left operand of `&&`: pops it, and if it is false pushes it back and jumps.
+    And(u32),
+
+    /// Calls the value below this many arguments, replacing all of them with the result.
+    Call(u32),
+    /// Creates a closure over the function with this index in the function table.
+    Closure(u32),
+    Return,
+
+    List(u32),
+    /// Pushes an empty map.
+    Map,
+    /// Pops a value and stores it in the map below it, under a key given as a string constant.
+    MapEntry(u32),
+    Index,
+    /// Pops a value, an index and a container, and stores the value in the container.
+    SetIndex,
+    Slice { start: bool, end: bool },
+
+    /// Pops the end and start of a range and keeps an iterator over it in
+    /// one of the function's iterator slots.
+    RangeIterator(u32),
+    /// Pops a list or map and keeps an iterator over it in an iterator slot.
+    ValueIterator(u32),
+    /// Pushes the next item of the iterator in a slot, or jumps once it is done.
+    IterateNext { iterator: u32, done: u32 },
+
+    /// Raised in place of a store the compiler already knows is not allowed.
+    ImmutableAssignment(u32),
+    ReturnOutsideFunction,
+    BreakOutsideLoop(Option<u32>),
+    ContinueOutsideLoop(Option<u32>),
+}
+
+impl Instruction {
+    /// How many values the instruction takes off the operand stack and how
+    /// many it leaves there when it does not jump. Instructions that end the
+    /// function, or always fail, leave nothing. `None` if the counts don't
+    /// fit in a `u32`, which only a damaged or hostile file can ask for.
+    pub fn stack_effect(&self) -> Option<(u32, u32)> {
+        Some(match *self {
+            Instruction::Constant(_) | Instruction::Nil | Instruction::Map | Instruction::GetLocal(_) | Instruction::GetUpvalue(_)
+            | Instruction::GetGlobal(_) | Instruction::Closure(_) | Instruction::IterateNext { .. } => (0, 1),
+            Instruction::Jump(_) | Instruction::BreakOutsideLoop(_) | Instruction::ContinueOutsideLoop(_) => (0, 0),
+            Instruction::Unary(_) | Instruction::CheckBoolean(_) => (1, 1),
+            Instruction::Pop | Instruction::SetLocal(_) | Instruction::DeclareLocal(_) | Instruction::SetUpvalue(_)
+            | Instruction::SetGlobal(_) | Instruction::DefineGlobal { .. } | Instruction::JumpIfFalse(..)
+            | Instruction::Or(_) | Instruction::And(_) | Instruction::Return | Instruction::ValueIterator(_)
+            | Instruction::ImmutableAssignment(_) | Instruction::ReturnOutsideFunction => (1, 0),
+            Instruction::Swap => (2, 2),
+            Instruction::Binary(_) | Instruction::Index | Instruction::MapEntry(_) => (2, 1),
+            Instruction::RangeIterator(_) => (2, 0),
+            Instruction::SetIndex => (3, 0),
+            Instruction::PopN(n) => (n, 0),
+            Instruction::Pick(n) => (n.checked_add(1)?, n.checked_add(2)?),
+            Instruction::Call(n) => (n.checked_add(1)?, 1),
+            Instruction::List(n) => (n, 1),
+            Instruction::Slice { start, end } => (1 + start as u32 + end as u32, 1),
+        })
+    }
+}
+
+/// Where a value had to be a boolean, for the error raised when it is not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    If,
+    While,
+    Or,
+    And,
+}
+
+impl Condition {
+    pub fn context(&self) -> &'static str {
+        match self {
+            Condition::If => "as an `if` condition",
+            Condition::While => "as a `while` condition",
+            Condition::Or => "as an operand of `||`",
+            Condition::And => "as an operand of `&&`",
+        }
+    }
+}
+
+/// Where a closure finds a variable of an enclosing function when it is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capture {
+    /// A local slot of the function creating the closure.
+    Local(u32),
+    /// One of the upvalues of the function creating the closure.
+    Upvalue(u32),
+}
+
+/// The compiled form of one function, or of the top level of a script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prototype {
+    pub name: Option<Rc<str>>,
+    pub arity: u32,
+    /// How many local slots a call needs; the parameters take the first ones.
+    pub slots: u32,
+    /// How many `for` loops can be running at once in the function, each
+    /// needing a slot for its iterator.
+    pub iterators: u32,
+    pub captures: Vec<Capture>,
+    pub code: Vec<Instruction>,
+    /// The source span of every instruction, for error messages.
+    pub spans: Vec<Span>,
+}
+
+/// A compiled program: the constants its code refers to and every function
+/// in it. The first function is the top level of the script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bytecode {
+    pub constants: Vec<DataType>,
+    pub functions: Vec<Prototype>,
+}
+
+impl Bytecode {
+    pub fn main(&self) -> &Prototype {
+        &self.functions[0]
+    }
+}
@@ -0,0 +1,599 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::compiler::bytecode::{Bytecode, Capture, Condition, Instruction, Prototype};
+use crate::datatypes::datatypes::{DataType, NumberType};
+use crate::lexer::span::Span;
+use crate::operators::operators::BinaryOperator;
+use crate::parser::expression::{Block, Expr, FunctionLiteral};
+use crate::parser::identifier::Identifier;
+use crate::parser::parser::Program;
+use crate::parser::statement::{AssignStatement, AssignTarget, ForStatement, Iterable, LetStatement, Stmt, WhileStatement};
+
+/// Lowers a parsed program to bytecode for the `vm`. Running the result gives
+/// the same bindings and the same errors, at the same spans, as running the
+/// program with the tree-walking `Interpreter`.
+///
+/// Top-level `let`s become globals, looked up by name at run time like the
+/// interpreter does. Every other variable is resolved here, to a local slot
+/// or to a variable captured from an enclosing function, so a function can
+/// only refer to locals declared before it, as the resolver makes the
+/// interpreter do too. In both, `let f = fn ...` declares `f` first so the
+/// function can call itself.
+pub fn compile(program: &Program) -> Bytecode {
+    let mut compiler = Compiler {
+        constants: Vec::new(),
+        constant_indices: HashMap::new(),
+        // The top level takes the first entry once it is done.
+        functions: vec![FunctionState::new(None, 0).prototype],
+        states: vec![FunctionState::new(None, 0)],
+    };
+
+    for statement in program.statements() {
+        compiler.statement(statement);
+    }
+    compiler.emit(Instruction::Nil, Span::default());
+    compiler.emit(Instruction::Return, Span::default());
+
+    let main = compiler.states.pop().expect("the top level is compiled last");
+    compiler.functions[0] = main.prototype;
+
+    Bytecode { constants: compiler.constants, functions: compiler.functions }
+}
+
+/// Constants are deduplicated by value; floats by their bits, so `0.0` and
+/// `-0.0` stay apart.
+#[derive(PartialEq, Eq, Hash)]
+enum ConstantKey {
+    Integer(i64),
+    Float(u64),
+    Boolean(bool),
+    String(Rc<str>),
+}
+
+struct Local {
+    name: Rc<str>,
+    depth: usize,
+    slot: u32,
+    mutable: bool,
+}
+
+struct Upvalue {
+    name: Rc<str>,
+    capture: Capture,
+}
+
+/// A loop being compiled, for the `break`s and `continue`s inside it.
+struct Loop {
+    label: Option<Rc<str>>,
+    /// How many values were on the operand stack when the loop started; a
+    /// `break` in the middle of an expression pops the ones above.
+    stack_depth: u32,
+    start: u32,
+    exits: Vec<usize>,
+}
+
+/// The function currently being compiled, with everything needed to resolve
+/// the names used in it.
+struct FunctionState {
+    prototype: Prototype,
+    locals: Vec<Local>,
+    upvalues: Vec<Upvalue>,
+    loops: Vec<Loop>,
+    scope_depth: usize,
+    /// The first free slot at the start of every open scope, so slots are
+    /// reused once the scope that took them ends.
+    scope_slots: Vec<u32>,
+    next_slot: u32,
+    /// How many `for` loops enclose the code being compiled.
+    open_iterators: u32,
+    stack_depth: u32,
+}
+
+impl FunctionState {
+    fn new(name: Option<Rc<str>>, arity: u32) -> FunctionState {
+        FunctionState {
+            prototype: Prototype { name, arity, slots: 0, iterators: 0, captures: Vec::new(), code: Vec::new(), spans: Vec::new() },
+            locals: Vec::new(),
+            upvalues: Vec::new(),
+            loops: Vec::new(),
+            scope_depth: 0,
+            scope_slots: Vec::new(),
+            next_slot: 0,
+            open_iterators: 0,
+            stack_depth: 0,
+        }
+    }
+
+    fn local(&self, name: &str) -> Option<&Local> {
+        self.locals.iter().rev().find(|local| &*local.name == name)
+    }
+}
+
+/// Where a name resolved to.
+enum Variable {
+    Local { slot: u32, mutable: bool },
+    Upvalue { index: u32, mutable: bool },
+    Global(u32),
+}
+
+struct Compiler {
+    constants: Vec<DataType>,
+    constant_indices: HashMap<ConstantKey, u32>,
+    functions: Vec<Prototype>,
+    /// The functions being compiled, innermost last.
+    states: Vec<FunctionState>,
+}
+
+impl Compiler {
+    fn state(&self) -> &FunctionState {
+        self.states.last().expect("a function is always being compiled")
+    }
+
+    fn state_mut(&mut self) -> &mut FunctionState {
+        self.states.last_mut().expect("a function is always being compiled")
+    }
+
+    /// Appends `instruction`, returning its index so a jump can be patched later.
+    fn emit(&mut self, instruction: Instruction, span: Span) -> usize {
+        let state = self.state_mut();
+        let (pops, pushes) = instruction.stack_effect().expect("the compiler's operands are counted in a u32");
+        state.stack_depth = state.stack_depth - pops + pushes;
+        state.prototype.code.push(instruction);
+        state.prototype.spans.push(span);
+        state.prototype.code.len() - 1
+    }
+
+    fn position(&self) -> u32 {
+        self.state().prototype.code.len() as u32
+    }
+
+    /// Points the jump at `at` to the next instruction to be emitted.
+    fn patch(&mut self, at: usize) {
+        let target = self.position();
+        match &mut self.state_mut().prototype.code[at] {
+            Instruction::Jump(to) | Instruction::JumpIfFalse(to, _) | Instruction::Or(to) | Instruction::And(to)
+            | Instruction::IterateNext { done: to, .. } => *to = target,
+            other => unreachable!("{:?} is not a jump", other)
+        }
+    }
+
+    fn constant(&mut self, value: DataType) -> u32 {
+        let key = match &value {
+            DataType::Number(NumberType::Integer(v)) => Some(ConstantKey::Integer(*v)),
+            DataType::Number(NumberType::Float(v)) => Some(ConstantKey::Float(v.to_bits())),
+            DataType::Boolean(v) => Some(ConstantKey::Boolean(*v)),
+            DataType::String(v) => Some(ConstantKey::String(Rc::clone(v))),
+            _ => None
+        };
+
+        if let Some(&index) = key.as_ref().and_then(|key| self.constant_indices.get(key)) {
+            return index;
+        }
+
+        let index = self.constants.len() as u32;
+        self.constants.push(value);
+        if let Some(key) = key {
+            self.constant_indices.insert(key, index);
+        }
+        index
+    }
+
+    fn name(&mut self, name: &Rc<str>) -> u32 {
+        self.constant(DataType::String(Rc::clone(name)))
+    }
+
+    fn begin_scope(&mut self) {
+        let state = self.state_mut();
+        state.scope_depth += 1;
+        state.scope_slots.push(state.next_slot);
+    }
+
+    fn end_scope(&mut self) {
+        let state = self.state_mut();
+        state.scope_depth -= 1;
+        state.next_slot = state.scope_slots.pop().expect("every scope that ends was begun");
+        while state.locals.last().is_some_and(|local| local.depth > state.scope_depth) {
+            state.locals.pop();
+        }
+    }
+
+    fn allocate_slot(&mut self) -> u32 {
+        let state = self.state_mut();
+        let slot = state.next_slot;
+        state.next_slot += 1;
+        state.prototype.slots = state.prototype.slots.max(state.next_slot);
+        slot
+    }
+
+    fn add_local(&mut self, name: &Rc<str>, mutable: bool) -> u32 {
+        let slot = self.allocate_slot();
+        let state = self.state_mut();
+        state.locals.push(Local { name: Rc::clone(name), depth: state.scope_depth, slot, mutable });
+        slot
+    }
+
+    /// The slot of `name` if it was already declared in the innermost scope.
+    fn declared_in_scope(&self, name: &str) -> Option<u32> {
+        let state = self.state();
+        state.locals.iter().rev()
+            .take_while(|local| local.depth == state.scope_depth)
+            .find(|local| &*local.name == name)
+            .map(|local| local.slot)
+    }
+
+    /// Whether a `let` here makes a global, as it does at the top level of the script.
+    fn at_top_level(&self) -> bool {
+        self.states.len() == 1 && self.state().scope_depth == 0
+    }
+
+    /// Pops the value on top of the stack into a new local called `name`.
+    /// Declaring a name again in the same scope reuses its slot, like the
+    /// interpreter replaces the binding, so closures that captured the old
+    /// one see the new value.
+    fn declare_local(&mut self, name: &Rc<str>, mutable: bool, span: Span) {
+        match self.declared_in_scope(name) {
+            Some(slot) => {
+                let state = self.state_mut();
+                if let Some(local) = state.locals.iter_mut().rev().find(|local| local.slot == slot) {
+                    local.mutable = mutable;
+                }
+                self.emit(Instruction::SetLocal(slot), span);
+            }
+            None => {
+                let slot = self.add_local(name, mutable);
+                self.emit(Instruction::DeclareLocal(slot), span);
+            }
+        }
+    }
+
+    fn resolve(&mut self, name: &Rc<str>) -> Variable {
+        if let Some(local) = self.state().local(name) {
+            return Variable::Local { slot: local.slot, mutable: local.mutable };
+        }
+        match self.resolve_upvalue(self.states.len() - 1, name) {
+            Some((index, mutable)) => Variable::Upvalue { index, mutable },
+            None => Variable::Global(self.name(name))
+        }
+    }
+
+    /// Finds `name` among the variables of the functions enclosing the one at
+    /// `level`, adding it to the captures of every function in between.
+    fn resolve_upvalue(&mut self, level: usize, name: &Rc<str>) -> Option<(u32, bool)> {
+        if level == 0 {
+            return None;
+        }
+
+        let (capture, mutable) = match self.states[level - 1].local(name) {
+            Some(local) => (Capture::Local(local.slot), local.mutable),
+            None => {
+                let (index, mutable) = self.resolve_upvalue(level - 1, name)?;
+                (Capture::Upvalue(index), mutable)
+            }
+        };
+
+        let upvalues = &mut self.states[level].upvalues;
+        if let Some(index) = upvalues.iter().position(|upvalue| upvalue.capture == capture && upvalue.name == *name) {
+            return Some((index as u32, mutable));
+        }
+        upvalues.push(Upvalue { name: Rc::clone(name), capture });
+        Some(((upvalues.len() - 1) as u32, mutable))
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        match statement {
+            Stmt::Let(s) => self.let_statement(s),
+            Stmt::Assign(s) => self.assign_statement(s),
+            Stmt::Expression(s) => {
+                self.expression(&s.expression);
+                self.emit(Instruction::Pop, s.span);
+            }
+            Stmt::Return(s) => {
+                match &s.value {
+                    Some(value) => self.expression(value),
+                    None => { self.emit(Instruction::Nil, s.span); }
+                }
+                let instruction = if self.states.len() > 1 { Instruction::Return } else { Instruction::ReturnOutsideFunction };
+                self.emit(instruction, s.span);
+            }
+            Stmt::Break(s) => self.loop_control(s.label.as_ref(), true, s.span),
+            Stmt::Continue(s) => self.loop_control(s.label.as_ref(), false, s.span),
+            Stmt::While(s) => self.while_statement(s),
+            Stmt::For(s) => self.for_statement(s),
+        }
+    }
+
+    fn let_statement(&mut self, s: &LetStatement) {
+        let name = &s.identifier.name;
+
+        if self.at_top_level() {
+            self.expression(&s.value);
+            let name = self.name(name);
+            self.emit(Instruction::DefineGlobal { name, mutable: s.mutable }, s.span);
+            return;
+        }
+
+        if matches!(s.value, Expr::Function(_)) && self.declared_in_scope(name).is_none() {
+            self.emit(Instruction::Nil, s.span);
+            self.declare_local(name, s.mutable, s.span);
+        }
+        self.expression(&s.value);
+        self.declare_local(name, s.mutable, s.span);
+    }
+
+    fn assign_statement(&mut self, s: &AssignStatement) {
+        match &s.target {
+            AssignTarget::Variable(variable) => {
+                self.expression(&s.value);
+                if let Some(op) = s.op {
+                    self.identifier(variable);
+                    self.emit(Instruction::Swap, s.span);
+                    self.emit(Instruction::Binary(op), s.span);
+                }
+                self.store(variable);
+            }
+            AssignTarget::Index(target) => {
+                self.expression(&target.target);
+                self.expression(&target.index);
+                self.store_item(s.op, &s.value, s.span, target.span);
+            }
+            AssignTarget::Field(target) => {
+                self.expression(&target.target);
+                let key = self.name(&target.field);
+                self.emit(Instruction::Constant(key), target.span);
+                self.store_item(s.op, &s.value, s.span, target.span);
+            }
+        }
+    }
+
+    /// With a container and an index on the stack, evaluates `value` and
+    /// stores it there, combining it with the current item first for a
+    /// compound assignment.
+    fn store_item(&mut self, op: Option<BinaryOperator>, value: &Expr, span: Span, target_span: Span) {
+        self.expression(value);
+        if let Some(op) = op {
+            self.emit(Instruction::Pick(2), target_span);
+            self.emit(Instruction::Pick(2), target_span);
+            self.emit(Instruction::Index, target_span);
+            self.emit(Instruction::Swap, span);
+            self.emit(Instruction::Binary(op), span);
+        }
+        self.emit(Instruction::SetIndex, target_span);
+    }
+
+    fn store(&mut self, variable: &Identifier) {
+        let instruction = match self.resolve(&variable.name) {
+            Variable::Local { slot, mutable: true } => Instruction::SetLocal(slot),
+            Variable::Upvalue { index, mutable: true } => Instruction::SetUpvalue(index),
+            Variable::Local { .. } | Variable::Upvalue { .. } => Instruction::ImmutableAssignment(self.name(&variable.name)),
+            Variable::Global(name) => Instruction::SetGlobal(name),
+        };
+        self.emit(instruction, variable.span);
+    }
+
+    fn loop_control(&mut self, label: Option<&Rc<str>>, is_break: bool, span: Span) {
+        let state = self.state();
+        let Some(target) = state.loops.iter().rposition(|l| label.is_none() || l.label.as_ref() == label) else {
+            let label = label.map(|label| self.name(label));
+            let instruction = if is_break { Instruction::BreakOutsideLoop(label) } else { Instruction::ContinueOutsideLoop(label) };
+            self.emit(instruction, span);
+            return;
+        };
+
+        // Whatever follows in this block is unreachable, but is still
+        // compiled as if the values popped here were there.
+        let stack_depth = state.stack_depth;
+        let excess = stack_depth - state.loops[target].stack_depth;
+        if excess > 0 {
+            self.emit(Instruction::PopN(excess), span);
+        }
+        if is_break {
+            let exit = self.emit(Instruction::Jump(0), span);
+            self.state_mut().loops[target].exits.push(exit);
+        } else {
+            let start = self.state().loops[target].start;
+            self.emit(Instruction::Jump(start), span);
+        }
+        self.state_mut().stack_depth = stack_depth;
+    }
+
+    fn begin_loop(&mut self, label: &Option<Rc<str>>, start: u32) {
+        let state = self.state_mut();
+        let stack_depth = state.stack_depth;
+        state.loops.push(Loop { label: label.clone(), stack_depth, start, exits: Vec::new() });
+    }
+
+    fn end_loop(&mut self) {
+        let finished = self.state_mut().loops.pop().expect("every loop that ends was begun");
+        for exit in finished.exits {
+            self.patch(exit);
+        }
+    }
+
+    fn while_statement(&mut self, s: &WhileStatement) {
+        let start = self.position();
+        self.expression(&s.condition);
+        let exit = self.emit(Instruction::JumpIfFalse(0, Condition::While), s.condition.span());
+
+        self.begin_loop(&s.label, start);
+        self.block(&s.body);
+        self.emit(Instruction::Pop, s.body.span);
+        self.emit(Instruction::Jump(start), s.span);
+        self.patch(exit);
+        self.end_loop();
+    }
+
+    /// The iterator takes the next free iterator slot until the loop ends;
+    /// the loop variable and the body get a fresh scope on every pass.
+    fn for_statement(&mut self, s: &ForStatement) {
+        let state = self.state_mut();
+        let iterator = state.open_iterators;
+        state.open_iterators += 1;
+        state.prototype.iterators = state.prototype.iterators.max(state.open_iterators);
+
+        match &s.iterable {
+            Iterable::Range { start, end } => {
+                self.expression(start);
+                self.expression(end);
+                self.emit(Instruction::RangeIterator(iterator), start.span().to(end.span()));
+            }
+            Iterable::Value(value) => {
+                self.expression(value);
+                self.emit(Instruction::ValueIterator(iterator), value.span());
+            }
+        }
+
+        let start = self.position();
+        self.begin_loop(&s.label, start);
+        let done = self.emit(Instruction::IterateNext { iterator, done: 0 }, s.span);
+
+        self.begin_scope();
+        self.declare_local(&s.variable.name, false, s.variable.span);
+        self.block_body(&s.body);
+        self.emit(Instruction::Pop, s.body.span);
+        self.end_scope();
+
+        self.emit(Instruction::Jump(start), s.span);
+        self.patch(done);
+        self.end_loop();
+        self.state_mut().open_iterators -= 1;
+    }
+
+    fn expression(&mut self, expression: &Expr) {
+        match expression {
+            Expr::Literal(e) => {
+                let index = self.constant(e.value.clone());
+                self.emit(Instruction::Constant(index), e.span);
+            }
+            Expr::Identifier(e) => self.identifier(e),
+            Expr::Binary(e) => match e.op {
+                BinaryOperator::Or | BinaryOperator::And => {
+                    let (instruction, condition) = match e.op {
+                        BinaryOperator::Or => (Instruction::Or(0), Condition::Or),
+                        _ => (Instruction::And(0), Condition::And),
+                    };
+                    self.expression(&e.l);
+                    let jump = self.emit(instruction, e.span);
+                    self.expression(&e.r);
+                    self.emit(Instruction::CheckBoolean(condition), e.span);
+                    self.patch(jump);
+                }
+                op => {
+                    self.expression(&e.l);
+                    self.expression(&e.r);
+                    self.emit(Instruction::Binary(op), e.span);
+                }
+            },
+            Expr::Unary(e) => {
+                self.expression(&e.inp);
+                self.emit(Instruction::Unary(e.op), e.span);
+            }
+            Expr::Call(e) => {
+                self.expression(&e.callee);
+                for argument in &e.arguments {
+                    self.expression(argument);
+                }
+                self.emit(Instruction::Call(e.arguments.len() as u32), e.span);
+            }
+            Expr::If(e) => {
+                self.expression(&e.condition);
+                let otherwise = self.emit(Instruction::JumpIfFalse(0, Condition::If), e.condition.span());
+                self.block(&e.consequence);
+                let end = self.emit(Instruction::Jump(0), e.span);
+
+                // Only one of the branches leaves its value on the stack.
+                self.patch(otherwise);
+                self.state_mut().stack_depth -= 1;
+                match &e.alternative {
+                    Some(alternative) => self.expression(alternative),
+                    None => { self.emit(Instruction::Nil, e.span); }
+                }
+                self.patch(end);
+            }
+            Expr::Function(e) => self.function(e),
+            Expr::Block(e) => self.block(e),
+            Expr::List(e) => {
+                for item in &e.items {
+                    self.expression(item);
+                }
+                self.emit(Instruction::List(e.items.len() as u32), e.span);
+            }
+            Expr::Index(e) => {
+                self.expression(&e.target);
+                self.expression(&e.index);
+                self.emit(Instruction::Index, e.span);
+            }
+            Expr::Slice(e) => {
+                self.expression(&e.target);
+                if let Some(start) = &e.start {
+                    self.expression(start);
+                }
+                if let Some(end) = &e.end {
+                    self.expression(end);
+                }
+                self.emit(Instruction::Slice { start: e.start.is_some(), end: e.end.is_some() }, e.span);
+            }
+            Expr::Map(e) => {
+                self.emit(Instruction::Map, e.span);
+                for (key, value) in &e.entries {
+                    let key = self.name(key);
+                    self.expression(value);
+                    self.emit(Instruction::MapEntry(key), e.span);
+                }
+            }
+            Expr::Field(e) => {
+                self.expression(&e.target);
+                let key = self.name(&e.field);
+                self.emit(Instruction::Constant(key), e.span);
+                self.emit(Instruction::Index, e.span);
+            }
+        }
+    }
+
+    fn identifier(&mut self, identifier: &Identifier) {
+        let instruction = match self.resolve(&identifier.name) {
+            Variable::Local { slot, .. } => Instruction::GetLocal(slot),
+            Variable::Upvalue { index, .. } => Instruction::GetUpvalue(index),
+            Variable::Global(name) => Instruction::GetGlobal(name),
+        };
+        self.emit(instruction, identifier.span);
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.begin_scope();
+        self.block_body(block);
+        self.end_scope();
+    }
+
+    /// The statements and value of `block`, in the current scope.
+    fn block_body(&mut self, block: &Block) {
+        for statement in &block.statements {
+            self.statement(statement);
+        }
+        match &block.value {
+            Some(value) => self.expression(value),
+            None => { self.emit(Instruction::Nil, block.span); }
+        }
+    }
+
+    /// Parameters and the body share the function's outermost scope. Every
+    /// parameter gets a slot, in order, even if a name repeats; the last one
+    /// with a name is the one the body sees.
+    fn function(&mut self, literal: &FunctionLiteral) {
+        let mut state = FunctionState::new(literal.name.clone(), literal.parameters.len() as u32);
+        state.scope_depth = 1;
+        self.states.push(state);
+
+        for parameter in &literal.parameters {
+            self.add_local(&parameter.name, false);
+        }
+        self.block_body(&literal.body);
+        self.emit(Instruction::Return, literal.body.span);
+
+        let mut finished = self.states.pop().expect("the function was pushed above");
+        finished.prototype.captures = finished.upvalues.iter().map(|upvalue| upvalue.capture).collect();
+        let index = self.functions.len() as u32;
+        self.functions.push(finished.prototype);
+
+        self.emit(Instruction::Closure(index), literal.span);
+    }
+}
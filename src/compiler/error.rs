@@ -0,0 +1,38 @@
+use std::fmt::{Display, Formatter};
+
+/// Why a compiled file was rejected instead of being run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    /// The file does not start with the bytecode magic number.
+    NotBytecode,
+    UnsupportedVersion { found: u16, supported: u16 },
+    /// The contents don't match the checksum in the header, so the file was
+    /// damaged after it was written.
+    ChecksumMismatch,
+    /// The file ended in the middle of something.
+    Truncated,
+    /// Something that doesn't decode, such as an unknown opcode.
+    Malformed(String),
+    /// Decodes fine, but could not be run safely, like a jump out of its function.
+    Invalid { function: usize, instruction: Option<usize>, reason: String },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::NotBytecode => write!(f, "not a compiled scriptx file"),
+            LoadError::UnsupportedVersion { found, supported } => {
+                write!(f, "bytecode version {} is not supported, expected version {}", found, supported)
+            }
+            LoadError::ChecksumMismatch => write!(f, "checksum mismatch, the file is corrupted"),
+            LoadError::Truncated => write!(f, "unexpected end of file"),
+            LoadError::Malformed(message) => write!(f, "malformed bytecode: {}", message),
+            LoadError::Invalid { function, instruction: Some(instruction), reason } => {
+                write!(f, "invalid bytecode in function {} at instruction {}: {}", function, instruction, reason)
+            }
+            LoadError::Invalid { function, instruction: None, reason } => {
+                write!(f, "invalid bytecode in function {}: {}", function, reason)
+            }
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod binary;
+pub mod bytecode;
+#[allow(clippy::module_inception)]
+pub mod compiler;
+pub mod error;
+pub mod verify;
@@ -0,0 +1,160 @@
+use crate::compiler::bytecode::{Bytecode, Capture, Instruction, Prototype};
+use crate::compiler::error::LoadError;
+use crate::datatypes::datatypes::DataType;
+
+/// Checks that bytecode which did not come straight from the compiler can be
+/// run without the `vm` indexing out of bounds or popping an empty stack:
+/// every operand refers to something that exists, and every path through a
+/// function sees the same stack depth at each instruction.
+pub fn verify(bytecode: &Bytecode) -> Result<(), LoadError> {
+    let main = bytecode.functions.first().ok_or_else(|| LoadError::Invalid {
+        function: 0,
+        instruction: None,
+        reason: String::from("there is no top level function"),
+    })?;
+    if main.arity != 0 || !main.captures.is_empty() {
+        return Err(invalid(0, None, "the top level takes no arguments or captures"));
+    }
+
+    for (index, function) in bytecode.functions.iter().enumerate() {
+        if function.arity > function.slots {
+            return Err(invalid(index, None, "there are more parameters than slots"));
+        }
+        if function.spans.len() != function.code.len() {
+            return Err(invalid(index, None, "there is not one span for each instruction"));
+        }
+        for (at, instruction) in function.code.iter().enumerate() {
+            operands(bytecode, function, *instruction).map_err(|reason| invalid(index, Some(at), reason))?;
+        }
+        let (slots, iterators) = used(bytecode, function);
+        if function.slots > slots.max(function.arity) || function.iterators > iterators {
+            return Err(invalid(index, None, "there are more slots than the code uses"));
+        }
+        stack_depths(function).map_err(|(at, reason)| invalid(index, at, reason))?;
+    }
+    Ok(())
+}
+
+fn invalid(function: usize, instruction: Option<usize>, reason: &str) -> LoadError {
+    LoadError::Invalid { function, instruction, reason: String::from(reason) }
+}
+
+fn operands(bytecode: &Bytecode, function: &Prototype, instruction: Instruction) -> Result<(), &'static str> {
+    let constant = |index: u32| match index < bytecode.constants.len() as u32 {
+        true => Ok(()),
+        false => Err("constant out of range"),
+    };
+    let name = |index: u32| match bytecode.constants.get(index as usize) {
+        Some(DataType::String(_)) => Ok(()),
+        _ => Err("a name is not a string constant"),
+    };
+    let slot = |index: u32| match index < function.slots {
+        true => Ok(()),
+        false => Err("local slot out of range"),
+    };
+    let upvalue = |index: u32| match index < function.captures.len() as u32 {
+        true => Ok(()),
+        false => Err("upvalue out of range"),
+    };
+    let iterator = |index: u32| match index < function.iterators {
+        true => Ok(()),
+        false => Err("iterator slot out of range"),
+    };
+    let target = |index: u32| match index < function.code.len() as u32 {
+        true => Ok(()),
+        false => Err("jump target out of range"),
+    };
+
+    match instruction {
+        Instruction::Constant(index) => constant(index),
+        Instruction::GetLocal(index) | Instruction::SetLocal(index) | Instruction::DeclareLocal(index) => slot(index),
+        Instruction::GetUpvalue(index) | Instruction::SetUpvalue(index) => upvalue(index),
+        Instruction::GetGlobal(index) | Instruction::SetGlobal(index) | Instruction::DefineGlobal { name: index, .. }
+        | Instruction::ImmutableAssignment(index) | Instruction::MapEntry(index) => name(index),
+        Instruction::BreakOutsideLoop(Some(label)) | Instruction::ContinueOutsideLoop(Some(label)) => name(label),
+        Instruction::Jump(index) | Instruction::JumpIfFalse(index, _) | Instruction::Or(index)
+        | Instruction::And(index) => target(index),
+        Instruction::RangeIterator(index) | Instruction::ValueIterator(index) => iterator(index),
+        Instruction::IterateNext { iterator: index, done } => iterator(index).and(target(done)),
+        Instruction::Closure(index) => {
+            let created = bytecode.functions.get(index as usize).ok_or("function out of range")?;
+            created.captures.iter().try_for_each(|capture| match *capture {
+                Capture::Local(index) => slot(index),
+                Capture::Upvalue(index) => upvalue(index),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// How many local and iterator slots the code of a function refers to,
+/// counting the locals that closures it creates capture. The `vm` allocates
+/// every slot a function asks for, so it must not ask for more.
+fn used(bytecode: &Bytecode, function: &Prototype) -> (u32, u32) {
+    let (mut slots, mut iterators) = (0, 0);
+    for instruction in &function.code {
+        match *instruction {
+            Instruction::GetLocal(index) | Instruction::SetLocal(index) | Instruction::DeclareLocal(index) => {
+                slots = slots.max(index + 1);
+            }
+            Instruction::RangeIterator(index) | Instruction::ValueIterator(index)
+            | Instruction::IterateNext { iterator: index, .. } => iterators = iterators.max(index + 1),
+            Instruction::Closure(created) => {
+                for capture in &bytecode.functions[created as usize].captures {
+                    if let Capture::Local(index) = *capture {
+                        slots = slots.max(index + 1);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    (slots, iterators)
+}
+
+/// Where control can go after an instruction, and the stack depth it gets
+/// there with, given the depth it started at. Fallthrough comes first.
+fn successors(at: usize, instruction: Instruction, depth: u32) -> Result<Vec<(usize, u32)>, &'static str> {
+    let (pops, pushes) = instruction.stack_effect().ok_or("an operand is too large")?;
+    let after = depth.checked_sub(pops).ok_or("pops more values than are on the stack")?;
+    let next = (at + 1, after.checked_add(pushes).ok_or("the stack grows too deep")?);
+    Ok(match instruction {
+        Instruction::Return | Instruction::ImmutableAssignment(_) | Instruction::ReturnOutsideFunction
+        | Instruction::BreakOutsideLoop(_) | Instruction::ContinueOutsideLoop(_) => vec![],
+        Instruction::Jump(target) => vec![(target as usize, depth)],
+        Instruction::JumpIfFalse(target, _) => vec![next, (target as usize, depth - 1)],
+        // The operand is pushed back when jumping.
+        Instruction::Or(target) | Instruction::And(target) => vec![next, (target as usize, depth)],
+        // Nothing is pushed once the iterator is done.
+        Instruction::IterateNext { done, .. } => vec![next, (done as usize, depth)],
+        _ => vec![next],
+    })
+}
+
+fn stack_depths(function: &Prototype) -> Result<(), (Option<usize>, &'static str)> {
+    if function.code.is_empty() {
+        return Err((None, "there is no code"));
+    }
+    let mut depths: Vec<Option<u32>> = vec![None; function.code.len()];
+    depths[0] = Some(0);
+    let mut pending = vec![0];
+
+    while let Some(at) = pending.pop() {
+        let instruction = function.code[at];
+        let depth = depths[at].expect("only reached instructions are pending");
+        for (next, next_depth) in successors(at, instruction, depth).map_err(|reason| (Some(at), reason))? {
+            match depths.get(next) {
+                None => return Err((Some(at), "runs past the end of the function")),
+                Some(None) => {
+                    depths[next] = Some(next_depth);
+                    pending.push(next);
+                }
+                Some(Some(known)) if *known != next_depth => {
+                    return Err((Some(next), "reached with different stack depths"));
+                }
+                Some(Some(_)) => {}
+            }
+        }
+    }
+    Ok(())
+}
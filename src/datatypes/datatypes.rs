@@ -1,165 +1,424 @@
-use std::collections::HashMap;
-use std::fmt::Debug;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use crate::builtins::builtins::Builtin;
+use crate::datatypes::function::{Closure, Function};
+use crate::datatypes::map::Map;
+use crate::interpreter::error::RuntimeErrorKind;
+use crate::lexer::span::Span;
 use crate::lexer::token::Token;
-use crate::operators::operators::{AddOperatorTrait, DivideOperatorTrait, MultiplyOperatorTrait, SubtractOperatorTrait};
-use crate::parser::expression::ExpressionLiteral;
-use crate::parser::identifier::Identifier;
+use crate::parser::error::{Expected, ParseError};
+use crate::operators::operators::{AddOperatorTrait, BinaryOperator, UnaryOperator, ComparisonOperatorTrait, DivideOperatorTrait, EqualityOperatorTrait, MultiplyOperatorTrait, NegateOperatorTrait, NotOperatorTrait, PlusOperatorTrait, RemainderOperatorTrait, SubtractOperatorTrait};
 
-#[derive(Debug, Clone)]
+/// Integers are 64-bit and never wrap: an operation whose result doesn't fit
+/// fails with an overflow error, rather than being promoted to a float.
+/// Floats are 64-bit IEEE 754 numbers.
+#[derive(Debug, Clone, PartialEq)]
 pub enum NumberType {
-    Integer(i32),
-    Float(f32)
+    Integer(i64),
+    Float(f64)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone, PartialEq)]
 pub enum DataType {
     Number(NumberType),
-    Boolean(bool)
+    Boolean(bool),
+    String(Rc<str>),
+    List(Rc<RefCell<Vec<DataType>>>),
+    Map(Rc<RefCell<Map>>),
+    Builtin(Builtin),
+    Function(Rc<Function>),
+    Closure(Rc<Closure>),
+    Nil
 }
 
 impl DataType {
-    pub fn from_token(t: &Token) -> DataType {
+    /// The value of a literal token found at `span`. A number literal fails
+    /// if it is out of range: an integer above `i64::MAX`, or a float too big
+    /// to be finite. As `-` is an operator, `i64::MIN` can only be computed.
+    pub fn from_token(t: &Token, span: Span) -> Result<DataType, ParseError> {
         match t {
-            &Token::TRUE => {return DataType::Boolean(true)},
-            &Token::FALSE => {return DataType::Boolean(false)},
-            Token::Number(val) => {
-                if val.contains('.') {
-                    let lit_val = val.parse::<f32>();
-                    return match lit_val {
-                        Ok(t) => DataType::Number(NumberType::Float(t)),
-                        _err => {panic!("Invalid floating point number")}
-                    }
-                } else {
-                    let lit_val = val.parse::<i32>();
-                    return match lit_val {
-                        Ok(t) =>  DataType::Number(NumberType::Integer(t)),
-                        _err => {panic!("Invalid number")}
-                    }
+            &Token::TRUE => Ok(DataType::Boolean(true)),
+            &Token::FALSE => Ok(DataType::Boolean(false)),
+            Token::String(val) => Ok(DataType::String(Rc::clone(val))),
+            Token::Number(val) if val.contains('.') => match val.parse::<f64>() {
+                Ok(v) if v.is_finite() => Ok(DataType::Number(NumberType::Float(v))),
+                _ => Err(ParseError::new(vec![Expected::FiniteFloat], t, span))
+            },
+            Token::Number(val) => match val.parse::<i64>() {
+                Ok(v) => Ok(DataType::Number(NumberType::Integer(v))),
+                Err(_) => Err(ParseError::new(vec![Expected::IntegerInRange], t, span))
+            },
+            _ => Err(ParseError::new(vec![Expected::Expression], t, span))
+        }
+    }
+
+    /// The name of the value's type as shown to script authors in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            DataType::Number(NumberType::Integer(_)) => "integer",
+            DataType::Number(NumberType::Float(_)) => "float",
+            DataType::Boolean(_) => "boolean",
+            DataType::String(_) => "string",
+            DataType::List(_) => "list",
+            DataType::Map(_) => "map",
+            DataType::Builtin(_) | DataType::Function(_) | DataType::Closure(_) => "function",
+            DataType::Nil => "nil",
+        }
+    }
+
+    pub fn new_list(items: Vec<DataType>) -> DataType {
+        DataType::List(Rc::new(RefCell::new(items)))
+    }
+
+    pub fn new_map(map: Map) -> DataType {
+        DataType::Map(Rc::new(RefCell::new(map)))
+    }
+
+    /// Applies `op` to two already evaluated operands. `&&` and `||` are
+    /// accepted too, but only as a strict operation on two booleans; it is up
+    /// to the caller to short-circuit before evaluating the right operand.
+    pub fn binary_operation(&self, op: BinaryOperator, other: DataType) -> Result<DataType, RuntimeErrorKind> {
+        match op {
+            BinaryOperator::Add => self.add(other),
+            BinaryOperator::Subtract => self.subtract(other),
+            BinaryOperator::Multiply => self.multiply(other),
+            BinaryOperator::Divide => self.divide(other),
+            BinaryOperator::Remainder => self.remainder(other),
+            BinaryOperator::Or | BinaryOperator::And => match (self, &other) {
+                (DataType::Boolean(a), DataType::Boolean(b)) => {
+                    Ok(DataType::Boolean(if op == BinaryOperator::Or { *a || *b } else { *a && *b }))
                 }
+                _ => Err(RuntimeErrorKind::TypeMismatch { operation: "logical operation", left: self.type_name(), right: other.type_name() })
             },
-            _ => {panic!("Invalid datatype")}
+            BinaryOperator::Equal => Ok(DataType::Boolean(self.equals(&other))),
+            BinaryOperator::NotEqual => Ok(DataType::Boolean(!self.equals(&other))),
+            BinaryOperator::LessThan => self.compare(&other).map(|o| DataType::Boolean(o == Some(Ordering::Less))),
+            BinaryOperator::GreaterThan => self.compare(&other).map(|o| DataType::Boolean(o == Some(Ordering::Greater))),
+            BinaryOperator::LessThanOrEqual => {
+                self.compare(&other).map(|o| DataType::Boolean(matches!(o, Some(Ordering::Less | Ordering::Equal))))
+            }
+            BinaryOperator::GreaterThanOrEqual => {
+                self.compare(&other).map(|o| DataType::Boolean(matches!(o, Some(Ordering::Greater | Ordering::Equal))))
+            }
+        }
+    }
+
+    /// `self[index]`. Lists and strings take an integer index, where a
+    /// negative one counts back from the end: `-1` is the last item. Maps take
+    /// a string key, which has to be present.
+    pub fn index(&self, index: &DataType) -> Result<DataType, RuntimeErrorKind> {
+        match self {
+            DataType::Map(map) => {
+                let key = string_key(self, index)?;
+                let value = map.borrow().get(key).cloned();
+                value.ok_or_else(|| RuntimeErrorKind::MissingKey(Rc::clone(key)))
+            }
+            DataType::List(items) => {
+                let items = items.borrow();
+                let position = resolve_index(integer_index(self, index)?, items.len())?;
+                Ok(items[position].clone())
+            }
+            DataType::String(s) => {
+                let length = s.chars().count();
+                let position = resolve_index(integer_index(self, index)?, length)?;
+                let ch = s.chars().nth(position).expect("position is in bounds");
+                Ok(DataType::String(Rc::from(ch.to_string())))
+            }
+            other => Err(RuntimeErrorKind::NotIndexable(other.type_name()))
+        }
+    }
+
+    /// `self[index] = value`. Only lists and maps can be changed this way; a
+    /// map gains the key if it didn't have it yet.
+    pub fn set_index(&self, index: &DataType, value: DataType) -> Result<(), RuntimeErrorKind> {
+        match self {
+            DataType::Map(map) => {
+                let key = string_key(self, index)?;
+                map.borrow_mut().insert(Rc::clone(key), value);
+                Ok(())
+            }
+            DataType::List(items) => {
+                let mut items = items.borrow_mut();
+                let position = resolve_index(integer_index(self, index)?, items.len())?;
+                items[position] = value;
+                Ok(())
+            }
+            DataType::String(_) => Err(RuntimeErrorKind::InvalidOperand { operation: "index assignment", operand: "string" }),
+            other => Err(RuntimeErrorKind::NotIndexable(other.type_name()))
+        }
+    }
+
+    /// `self[start..end]`, a copy of the items from `start` up to but not
+    /// including `end`. A missing bound means the start or end of the list, and
+    /// negative bounds count back from the end as they do for `index`.
+    pub fn slice(&self, start: Option<&DataType>, end: Option<&DataType>) -> Result<DataType, RuntimeErrorKind> {
+        let length = match self {
+            DataType::List(items) => items.borrow().len(),
+            DataType::String(s) => s.chars().count(),
+            DataType::Map(_) => return Err(RuntimeErrorKind::InvalidOperand { operation: "slicing", operand: "map" }),
+            other => return Err(RuntimeErrorKind::NotIndexable(other.type_name()))
+        };
+
+        let from = match start {
+            Some(start) => resolve_bound(integer_index(self, start)?, length)?,
+            None => 0
+        };
+        let to = match end {
+            Some(end) => resolve_bound(integer_index(self, end)?, length)?,
+            None => length
+        };
+
+        if from > to {
+            return Err(RuntimeErrorKind::SliceOutOfOrder { start: from as i64, end: to as i64 })
+        }
+
+        match self {
+            DataType::List(items) => Ok(DataType::new_list(items.borrow()[from..to].to_vec())),
+            DataType::String(s) => Ok(DataType::String(Rc::from(s.chars().skip(from).take(to - from).collect::<String>()))),
+            _ => unreachable!("checked above")
+        }
+    }
+
+    pub fn unary_operation(&self, op: UnaryOperator) -> Result<DataType, RuntimeErrorKind> {
+        match op {
+            UnaryOperator::Not => self.not(),
+            UnaryOperator::Negate => self.negate(),
+            UnaryOperator::Plus => self.plus(),
         }
     }
 }
 
-impl ExpressionLiteral for DataType {
-    fn value(&self, _mem_table: &HashMap<Identifier, Box<DataType>>) -> DataType {
-        self.clone()
+fn integer_index(container: &DataType, index: &DataType) -> Result<i64, RuntimeErrorKind> {
+    match index {
+        DataType::Number(NumberType::Integer(i)) => Ok(*i),
+        other => Err(RuntimeErrorKind::InvalidIndex { container: container.type_name(), index: other.type_name() })
     }
 }
 
-impl AddOperatorTrait for DataType {
-    fn add(&self, other: DataType) -> DataType {
-        return match self {
-            DataType::Number(one) => {
-                return match other {
-                    DataType::Number(other) => {
-                        return match one {
-                            NumberType::Integer(one_raw) => {
-                                match other {
-                                    NumberType::Integer(other_raw) => DataType::Number(NumberType::Integer(one_raw.clone() + other_raw)),
-                                    NumberType::Float(other_raw) => DataType::Number(NumberType::Float((one_raw.clone() as f32) + other_raw))
-                                }
-                            }
-                            NumberType::Float(one_raw) => {
-                                match other {
-                                    NumberType::Integer(other_raw) => DataType::Number(NumberType::Float(one_raw.clone() + (other_raw as f32))),
-                                    NumberType::Float(other_raw) => DataType::Number(NumberType::Float(one_raw.clone() + other_raw))
-                                }
-                            }
-                        }
+fn string_key<'a>(container: &DataType, key: &'a DataType) -> Result<&'a Rc<str>, RuntimeErrorKind> {
+    match key {
+        DataType::String(key) => Ok(key),
+        other => Err(RuntimeErrorKind::InvalidIndex { container: container.type_name(), index: other.type_name() })
+    }
+}
+
+/// The position `index` refers to in a sequence of `length` items, counting
+/// from the end if it is negative.
+pub fn resolve_index(index: i64, length: usize) -> Result<usize, RuntimeErrorKind> {
+    let position = if index < 0 { length as i64 + index } else { index };
+
+    if position < 0 || position >= length as i64 {
+        return Err(RuntimeErrorKind::IndexOutOfBounds { index, length })
+    }
+    Ok(position as usize)
+}
+
+/// Like `resolve_index`, but also accepts `length` itself, the position just
+/// past the last item, as slice ends and insertion points may use it.
+pub fn resolve_bound(index: i64, length: usize) -> Result<usize, RuntimeErrorKind> {
+    if index == length as i64 {
+        return Ok(length)
+    }
+    resolve_index(index, length)
+}
+
+/// Applies an arithmetic operation to two numbers. Integers stay integers
+/// (reporting overflow instead of wrapping); as soon as either side is a
+/// float, both are promoted to float.
+fn arithmetic(
+    one: &DataType,
+    other: &DataType,
+    operation: &'static str,
+    int_op: fn(i64, i64) -> Option<i64>,
+    float_op: fn(f64, f64) -> f64
+) -> Result<DataType, RuntimeErrorKind> {
+    match (one, other) {
+        (DataType::Number(one_num), DataType::Number(other_num)) => {
+            match (one_num, other_num) {
+                (NumberType::Integer(one_raw), NumberType::Integer(other_raw)) => {
+                    match int_op(*one_raw, *other_raw) {
+                        Some(v) => Ok(DataType::Number(NumberType::Integer(v))),
+                        None => Err(RuntimeErrorKind::Overflow { operation })
                     }
-                    DataType::Boolean(_) => {panic!("The operation addition is not defined for booleans")}
                 }
+                (NumberType::Integer(one_raw), NumberType::Float(other_raw)) => Ok(DataType::Number(NumberType::Float(float_op(*one_raw as f64, *other_raw)))),
+                (NumberType::Float(one_raw), NumberType::Integer(other_raw)) => Ok(DataType::Number(NumberType::Float(float_op(*one_raw, *other_raw as f64)))),
+                (NumberType::Float(one_raw), NumberType::Float(other_raw)) => Ok(DataType::Number(NumberType::Float(float_op(*one_raw, *other_raw)))),
             }
-            DataType::Boolean(_) => {panic!("The operation addition is not defined for booleans")}
         }
+        _ => Err(RuntimeErrorKind::TypeMismatch { operation, left: one.type_name(), right: other.type_name() })
     }
 }
 
-impl SubtractOperatorTrait for DataType {
-    fn subtract(&self, other: DataType) -> DataType {
-        return match self {
-            DataType::Number(one) => {
-                return match other {
-                    DataType::Number(other) => {
-                        return match one {
-                            NumberType::Integer(one_raw) => {
-                                match other {
-                                    NumberType::Integer(other_raw) => DataType::Number(NumberType::Integer(one_raw.clone() - other_raw)),
-                                    NumberType::Float(other_raw) => DataType::Number(NumberType::Float((one_raw.clone() as f32) - other_raw))
-                                }
-                            }
-                            NumberType::Float(one_raw) => {
-                                match other {
-                                    NumberType::Integer(other_raw) => DataType::Number(NumberType::Float(one_raw.clone() - (other_raw as f32))),
-                                    NumberType::Float(other_raw) => DataType::Number(NumberType::Float(one_raw.clone() - other_raw))
-                                }
-                            }
-                        }
-                    }
-                    DataType::Boolean(_) => {panic!("The operation subtraction is not defined for booleans")}
-                }
+impl AddOperatorTrait for DataType {
+    fn add(&self, other: DataType) -> Result<DataType, RuntimeErrorKind> {
+        match (self, &other) {
+            (DataType::String(one), DataType::String(other)) => {
+                let mut joined = String::with_capacity(one.len() + other.len());
+                joined.push_str(one);
+                joined.push_str(other);
+                Ok(DataType::String(Rc::from(joined)))
             }
-            DataType::Boolean(_) => {panic!("The operation subtraction is not defined for booleans")}
+            _ => arithmetic(self, &other, "addition", i64::checked_add, |a, b| a + b)
         }
     }
 }
 
+impl SubtractOperatorTrait for DataType {
+    fn subtract(&self, other: DataType) -> Result<DataType, RuntimeErrorKind> {
+        arithmetic(self, &other, "subtraction", i64::checked_sub, |a, b| a - b)
+    }
+}
+
 impl MultiplyOperatorTrait for DataType {
-    fn multiply(&self, other: DataType) -> DataType {
-        return match self {
-            DataType::Number(one) => {
-                return match other {
-                    DataType::Number(other) => {
-                        return match one {
-                            NumberType::Integer(one_raw) => {
-                                match other {
-                                    NumberType::Integer(other_raw) => DataType::Number(NumberType::Integer(one_raw.clone() * other_raw)),
-                                    NumberType::Float(other_raw) => DataType::Number(NumberType::Float((one_raw.clone() as f32) * other_raw))
-                                }
-                            }
-                            NumberType::Float(one_raw) => {
-                                match other {
-                                    NumberType::Integer(other_raw) => DataType::Number(NumberType::Float(one_raw.clone() * (other_raw as f32))),
-                                    NumberType::Float(other_raw) => DataType::Number(NumberType::Float(one_raw.clone() * other_raw))
-                                }
-                            }
-                        }
-                    }
-                    DataType::Boolean(_) => {panic!("The operation multiplication is not defined for booleans")}
-                }
-            }
-            DataType::Boolean(_) => {panic!("The operation multiplication is not defined for booleans")}
-        }
+    fn multiply(&self, other: DataType) -> Result<DataType, RuntimeErrorKind> {
+        arithmetic(self, &other, "multiplication", i64::checked_mul, |a, b| a * b)
     }
 }
 
 impl DivideOperatorTrait for DataType {
-    fn divide(&self, other: DataType) -> DataType {
-        return match self {
-            DataType::Number(one) => {
-                return match other {
-                    DataType::Number(other) => {
-                        return match one {
-                            NumberType::Integer(one_raw) => {
-                                match other {
-                                    NumberType::Integer(other_raw) => DataType::Number(NumberType::Integer(one_raw.clone() / other_raw)),
-                                    NumberType::Float(other_raw) => DataType::Number(NumberType::Float((one_raw.clone() as f32) / other_raw))
-                                }
-                            }
-                            NumberType::Float(one_raw) => {
-                                match other {
-                                    NumberType::Integer(other_raw) => DataType::Number(NumberType::Float(one_raw.clone() / (other_raw as f32))),
-                                    NumberType::Float(other_raw) => DataType::Number(NumberType::Float(one_raw.clone() / other_raw))
-                                }
-                            }
-                        }
-                    }
-                    DataType::Boolean(_) => {panic!("The operation division is not defined for booleans")}
-                }
+    fn divide(&self, other: DataType) -> Result<DataType, RuntimeErrorKind> {
+        match (self, &other) {
+            (DataType::Number(_), DataType::Number(NumberType::Integer(0))) => Err(RuntimeErrorKind::DivisionByZero),
+            (DataType::Number(_), DataType::Number(NumberType::Float(v))) if *v == 0.0 => Err(RuntimeErrorKind::DivisionByZero),
+            _ => arithmetic(self, &other, "division", i64::checked_div, |a, b| a / b)
+        }
+    }
+}
+
+/// The remainder of a truncating division, so it takes the sign of the left
+/// operand: `-7 % 3` is `-1`.
+impl RemainderOperatorTrait for DataType {
+    fn remainder(&self, other: DataType) -> Result<DataType, RuntimeErrorKind> {
+        match (self, &other) {
+            (DataType::Number(_), DataType::Number(NumberType::Integer(0))) => Err(RuntimeErrorKind::DivisionByZero),
+            (DataType::Number(_), DataType::Number(NumberType::Float(v))) if *v == 0.0 => Err(RuntimeErrorKind::DivisionByZero),
+            _ => arithmetic(self, &other, "remainder", i64::checked_rem, |a, b| a % b)
+        }
+    }
+}
+
+impl NotOperatorTrait for DataType {
+    fn not(&self) -> Result<DataType, RuntimeErrorKind> {
+        match self {
+            DataType::Boolean(b) => Ok(DataType::Boolean(!b)),
+            other => Err(RuntimeErrorKind::InvalidOperand { operation: "logical not", operand: other.type_name() })
+        }
+    }
+}
+
+impl NegateOperatorTrait for DataType {
+    fn negate(&self) -> Result<DataType, RuntimeErrorKind> {
+        match self {
+            DataType::Number(NumberType::Integer(i)) => match i.checked_neg() {
+                Some(v) => Ok(DataType::Number(NumberType::Integer(v))),
+                None => Err(RuntimeErrorKind::Overflow { operation: "negation" })
+            },
+            DataType::Number(NumberType::Float(f)) => Ok(DataType::Number(NumberType::Float(-f))),
+            other => Err(RuntimeErrorKind::InvalidOperand { operation: "negation", operand: other.type_name() })
+        }
+    }
+}
+
+impl PlusOperatorTrait for DataType {
+    fn plus(&self) -> Result<DataType, RuntimeErrorKind> {
+        match self {
+            DataType::Number(_) => Ok(self.clone()),
+            other => Err(RuntimeErrorKind::InvalidOperand { operation: "unary plus", operand: other.type_name() })
+        }
+    }
+}
+
+/// Structural equality. Numbers are equal when they are numerically equal,
+/// whether integer or float; values of different types are never equal.
+impl EqualityOperatorTrait for DataType {
+    fn equals(&self, other: &DataType) -> bool {
+        self.equals_within(other, &mut Vec::new())
+    }
+}
+
+impl DataType {
+    /// `equals`, for a comparison already inside the lists and maps in
+    /// `comparing`. Meeting a pair that is being compared again means the
+    /// values are cyclic, and nothing found on the way around has differed.
+    fn equals_within(&self, other: &DataType, comparing: &mut Vec<(*const (), *const ())>) -> bool {
+        let pair = match (self, other) {
+            (DataType::List(a), DataType::List(b)) => (Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ()),
+            (DataType::Map(a), DataType::Map(b)) => (Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ()),
+            (DataType::Number(NumberType::Integer(a)), DataType::Number(NumberType::Float(b))) => return (*a as f64) == *b,
+            (DataType::Number(NumberType::Float(a)), DataType::Number(NumberType::Integer(b))) => return *a == (*b as f64),
+            _ => return self == other
+        };
+        if comparing.contains(&pair) {
+            return true;
+        }
+
+        comparing.push(pair);
+        let equal = match (self, other) {
+            (DataType::List(a), DataType::List(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| x.equals_within(y, comparing))
             }
-            DataType::Boolean(_) => {panic!("The operation division is not defined for booleans")}
+            (DataType::Map(a), DataType::Map(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len() && a.entries().all(|(key, x)| b.get(key).is_some_and(|y| x.equals_within(y, comparing)))
+            }
+            _ => unreachable!("only lists and maps are compared here")
+        };
+        comparing.pop();
+        equal
+    }
+}
+
+thread_local! {
+    /// The lists and maps being printed, outermost first.
+    static PRINTING: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Like a derived `Debug`, except that a list or map inside itself is shown
+/// as `[...]` or `{...}` instead of being printed forever.
+impl Debug for DataType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let pointer = match self {
+            DataType::Number(n) => return f.debug_tuple("Number").field(n).finish(),
+            DataType::Boolean(b) => return f.debug_tuple("Boolean").field(b).finish(),
+            DataType::String(s) => return f.debug_tuple("String").field(s).finish(),
+            DataType::Builtin(b) => return f.debug_tuple("Builtin").field(b).finish(),
+            DataType::Function(function) => return f.debug_tuple("Function").field(function).finish(),
+            DataType::Closure(closure) => return f.debug_tuple("Closure").field(closure).finish(),
+            DataType::Nil => return f.write_str("Nil"),
+            DataType::List(list) => Rc::as_ptr(list) as *const (),
+            DataType::Map(map) => Rc::as_ptr(map) as *const (),
+        };
+        if PRINTING.with_borrow(|printing| printing.contains(&pointer)) {
+            return f.write_str(if let DataType::List(_) = self { "[...]" } else { "{...}" });
+        }
+
+        PRINTING.with_borrow_mut(|printing| printing.push(pointer));
+        let result = match self {
+            DataType::List(list) => f.debug_tuple("List").field(list).finish(),
+            DataType::Map(map) => f.debug_tuple("Map").field(map).finish(),
+            _ => unreachable!("only lists and maps are tracked")
+        };
+        PRINTING.with_borrow_mut(|printing| printing.pop());
+        result
+    }
+}
+
+/// Numbers are ordered numerically (promoting an integer to float when
+/// compared with a float) and strings lexicographically. Any other pairing
+/// has no order.
+impl ComparisonOperatorTrait for DataType {
+    fn compare(&self, other: &DataType) -> Result<Option<Ordering>, RuntimeErrorKind> {
+        match (self, other) {
+            (DataType::Number(NumberType::Integer(a)), DataType::Number(NumberType::Integer(b))) => Ok(Some(a.cmp(b))),
+            (DataType::Number(NumberType::Integer(a)), DataType::Number(NumberType::Float(b))) => Ok((*a as f64).partial_cmp(b)),
+            (DataType::Number(NumberType::Float(a)), DataType::Number(NumberType::Integer(b))) => Ok(a.partial_cmp(&(*b as f64))),
+            (DataType::Number(NumberType::Float(a)), DataType::Number(NumberType::Float(b))) => Ok(a.partial_cmp(b)),
+            (DataType::String(a), DataType::String(b)) => Ok(Some(a.cmp(b))),
+            _ => Err(RuntimeErrorKind::TypeMismatch { operation: "comparison", left: self.type_name(), right: other.type_name() })
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,130 @@
+use std::cell::{Cell, RefCell};
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use crate::compiler::bytecode::{Bytecode, Prototype};
+use crate::datatypes::datatypes::DataType;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::error::{RuntimeError, RuntimeErrorKind, Unwind};
+use crate::lexer::span::Span;
+use crate::parser::expression::FunctionLiteral;
+
+/// How many script function calls may be active at once before a call fails
+/// with a stack overflow error, in the interpreter and the `vm` alike.
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+/// The native stack, in bytes, that the interpreter needs to reach
+/// `MAX_CALL_DEPTH` without exhausting it, even when every call is nested as
+/// deeply as the parser allows. That takes about half of this. The `vm` does
+/// not recurse to make calls and gets by with a default stack. `main` runs
+/// scripts on a thread this big; other hosts of the interpreter should too.
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+thread_local! {
+    static CALL_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A function value created by evaluating a `fn(...) { ... }` literal. It
+/// keeps the environment it was created in, so its body can see the
+/// variables that were in scope at that point.
+pub struct Function {
+    pub declaration: Rc<FunctionLiteral>,
+    pub env: Environment,
+}
+
+impl Function {
+    pub fn name(&self) -> Option<&Rc<str>> {
+        self.declaration.name.as_ref()
+    }
+
+    pub fn display_name(&self) -> Rc<str> {
+        self.name().cloned().unwrap_or_else(|| Rc::from("<anonymous fn>"))
+    }
+
+    pub fn call(&self, arguments: Vec<DataType>, span: Span) -> Result<DataType, RuntimeError> {
+        let parameters = &self.declaration.parameters;
+
+        if arguments.len() != parameters.len() {
+            let kind = RuntimeErrorKind::ArgumentCountMismatch {
+                function: self.display_name(),
+                expected: parameters.len(),
+                found: arguments.len()
+            };
+            return Err(RuntimeError::new(kind, span))
+        }
+
+        let depth = CALL_DEPTH.get();
+        if depth >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::new(RuntimeErrorKind::StackOverflow { depth: MAX_CALL_DEPTH }, span))
+        }
+
+        let mut env = Environment::enclosed(&self.env);
+        for (parameter, argument) in parameters.iter().zip(arguments) {
+            parameter.declare(&mut env, argument, false);
+        }
+
+        CALL_DEPTH.set(depth + 1);
+        let result = self.declaration.body.evaluate(&mut env);
+        CALL_DEPTH.set(depth);
+
+        match result {
+            Ok(value) => Ok(value),
+            Err(Unwind::Return(value, _)) => Ok(value),
+            Err(unwind) => Err(unwind.into_error()),
+        }
+    }
+}
+
+impl Debug for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "<fn {}>", name),
+            None => write!(f, "<fn>")
+        }
+    }
+}
+
+/// Two function values are equal only if they are the same value.
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// A function value created by the `vm` from a compiled function. Instead of
+/// a whole environment it keeps just the variables of enclosing functions
+/// that its code uses, each shared with the function it was captured from.
+pub struct Closure {
+    pub bytecode: Rc<Bytecode>,
+    pub function: usize,
+    pub upvalues: Vec<Rc<RefCell<DataType>>>,
+}
+
+impl Closure {
+    pub fn prototype(&self) -> &Prototype {
+        &self.bytecode.functions[self.function]
+    }
+
+    pub fn name(&self) -> Option<&Rc<str>> {
+        self.prototype().name.as_ref()
+    }
+
+    pub fn display_name(&self) -> Rc<str> {
+        self.name().cloned().unwrap_or_else(|| Rc::from("<anonymous fn>"))
+    }
+}
+
+impl Debug for Closure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "<fn {}>", name),
+            None => write!(f, "<fn>")
+        }
+    }
+}
+
+/// Two closures are equal only if they are the same value.
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use crate::datatypes::datatypes::DataType;
+
+/// A string-keyed map that remembers the order its keys were first inserted
+/// in, so iterating over it or printing it is deterministic. Replacing the
+/// value of a key keeps its place; removing a key and inserting it again moves
+/// it to the end.
+#[derive(Clone, Default)]
+pub struct Map {
+    entries: Vec<(Rc<str>, DataType)>,
+    positions: HashMap<Rc<str>, usize>,
+}
+
+impl Map {
+    pub fn new() -> Map {
+        Map::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&DataType> {
+        self.positions.get(key).map(|&position| &self.entries[position].1)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    /// Sets `key` to `value`, returning the value it replaced, if any.
+    pub fn insert(&mut self, key: Rc<str>, value: DataType) -> Option<DataType> {
+        match self.positions.get(&key) {
+            Some(&position) => Some(std::mem::replace(&mut self.entries[position].1, value)),
+            None => {
+                self.positions.insert(Rc::clone(&key), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<DataType> {
+        let position = self.positions.remove(key)?;
+        let (_, value) = self.entries.remove(position);
+
+        for (key, _) in &self.entries[position..] {
+            *self.positions.get_mut(key).expect("every entry has a position") -= 1;
+        }
+
+        Some(value)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Rc<str>> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &DataType> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&Rc<str>, &DataType)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+impl Debug for Map {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.entries()).finish()
+    }
+}
+
+/// Two maps are equal when they have the same keys with equal values, in
+/// whatever order.
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.entries().all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
@@ -1 +1,4 @@
-pub mod datatypes;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod datatypes;
+pub mod function;
+pub mod map;
\ No newline at end of file
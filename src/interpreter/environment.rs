@@ -0,0 +1,151 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::datatypes::datatypes::DataType;
+use crate::interpreter::error::RuntimeErrorKind;
+
+#[derive(Debug)]
+struct Binding {
+    value: DataType,
+    mutable: bool,
+}
+
+/// Globals and builtins are bound by name in `values`. Locals go into
+/// `slots` instead, at the position the resolver picked for them.
+#[derive(Debug, Default)]
+struct Scope {
+    values: HashMap<Rc<str>, Binding>,
+    slots: Vec<Option<Binding>>,
+    parent: Option<Rc<RefCell<Scope>>>,
+}
+
+/// A chain of lexical scopes. The innermost scope is the one new bindings go
+/// into; lookups walk outwards through the parents until a binding is found.
+///
+/// Cloning an `Environment` shares the underlying scopes rather than copying
+/// them, which is what lets a closure keep the scope it was defined in alive.
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    scope: Rc<RefCell<Scope>>,
+}
+
+impl Environment {
+    pub fn new() -> Environment {
+        Environment { scope: Rc::new(RefCell::new(Scope::default())) }
+    }
+
+    /// Creates a new environment whose outermost visible scope is `parent`.
+    pub fn enclosed(parent: &Environment) -> Environment {
+        let mut env = parent.clone();
+        env.push_scope();
+        env
+    }
+
+    pub fn push_scope(&mut self) {
+        let scope = Scope { values: HashMap::new(), slots: Vec::new(), parent: Some(Rc::clone(&self.scope)) };
+        self.scope = Rc::new(RefCell::new(scope));
+    }
+
+    pub fn pop_scope(&mut self) {
+        let parent = self.scope.borrow().parent.clone();
+        match parent {
+            None => { panic!("Cannot pop the global scope") }
+            Some(parent) => { self.scope = parent }
+        }
+    }
+
+    /// Binds `name` in the innermost scope, shadowing any outer binding. The
+    /// binding can't be assigned to afterwards.
+    pub fn define(&mut self, name: Rc<str>, value: DataType) {
+        self.scope.borrow_mut().values.insert(name, Binding { value, mutable: false });
+    }
+
+    /// Like `define`, but the binding can be updated with `assign`.
+    pub fn define_mutable(&mut self, name: Rc<str>, value: DataType) {
+        self.scope.borrow_mut().values.insert(name, Binding { value, mutable: true });
+    }
+
+    /// Binds a resolved local in the innermost scope, replacing whatever the
+    /// slot held before.
+    pub fn declare(&mut self, slot: usize, value: DataType, mutable: bool) {
+        let slots = &mut self.scope.borrow_mut().slots;
+        if slots.len() <= slot {
+            slots.resize_with(slot + 1, || None);
+        }
+        slots[slot] = Some(Binding { value, mutable });
+    }
+
+    /// The scope `depth` steps out from the innermost one.
+    fn enclosing(&self, depth: usize) -> Rc<RefCell<Scope>> {
+        let mut scope = Rc::clone(&self.scope);
+        for _ in 0..depth {
+            let parent = scope.borrow().parent.clone().expect("resolved depths stay within the scope chain");
+            scope = parent;
+        }
+        scope
+    }
+
+    /// Reads a resolved local, or `None` if its declaration has not run yet.
+    pub fn get(&self, depth: usize, slot: usize) -> Option<DataType> {
+        let scope = self.enclosing(depth);
+        let scope = scope.borrow();
+        scope.slots.get(slot)?.as_ref().map(|binding| binding.value.clone())
+    }
+
+    /// Like `assign`, for a resolved local. `name` is only used for errors.
+    pub fn assign_slot(&mut self, depth: usize, slot: usize, name: &str, value: DataType) -> Result<(), RuntimeErrorKind> {
+        let scope = self.enclosing(depth);
+        let mut scope = scope.borrow_mut();
+        match scope.slots.get_mut(slot).and_then(Option::as_mut) {
+            None => Err(RuntimeErrorKind::UndeclaredAssignment(Rc::from(name))),
+            Some(binding) if !binding.mutable => Err(RuntimeErrorKind::ImmutableAssignment(Rc::from(name))),
+            Some(binding) => {
+                binding.value = value;
+                Ok(())
+            }
+        }
+    }
+
+    /// Updates the nearest existing binding of `name`, failing if there is
+    /// none or if it was not declared mutable.
+    pub fn assign(&mut self, name: &str, value: DataType) -> Result<(), RuntimeErrorKind> {
+        let mut scope = Rc::clone(&self.scope);
+        loop {
+            if let Some(binding) = scope.borrow_mut().values.get_mut(name) {
+                if !binding.mutable {
+                    return Err(RuntimeErrorKind::ImmutableAssignment(Rc::from(name)));
+                }
+                binding.value = value;
+                return Ok(());
+            }
+            let parent = scope.borrow().parent.clone();
+            match parent {
+                None => { return Err(RuntimeErrorKind::UndeclaredAssignment(Rc::from(name))) }
+                Some(parent) => { scope = parent }
+            }
+        }
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<DataType> {
+        let mut scope = Rc::clone(&self.scope);
+        loop {
+            if let Some(binding) = scope.borrow().values.get(name) {
+                return Some(binding.value.clone());
+            }
+            let parent = scope.borrow().parent.clone();
+            match parent {
+                None => { return None }
+                Some(parent) => { scope = parent }
+            }
+        }
+    }
+
+    /// The bindings of the innermost scope, sorted by name so the output is stable.
+    pub fn bindings(&self) -> Vec<(Rc<str>, DataType)> {
+        let mut bindings: Vec<(Rc<str>, DataType)> = self.scope.borrow().values.iter()
+            .map(|(k, v)| (Rc::clone(k), v.value.clone()))
+            .collect();
+        bindings.sort_by(|a, b| a.0.cmp(&b.0));
+        bindings
+    }
+}
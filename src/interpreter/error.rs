@@ -0,0 +1,121 @@
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+use crate::datatypes::datatypes::DataType;
+use crate::lexer::span::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    TypeMismatch { operation: &'static str, left: &'static str, right: &'static str },
+    InvalidOperand { operation: &'static str, operand: &'static str },
+    NotABoolean { context: &'static str, found: &'static str },
+    UndefinedVariable(Rc<str>),
+    UndeclaredAssignment(Rc<str>),
+    ImmutableAssignment(Rc<str>),
+    DivisionByZero,
+    Overflow { operation: &'static str },
+    NotCallable(&'static str),
+    ArgumentCountMismatch { function: Rc<str>, expected: usize, found: usize },
+    InvalidArgument { function: &'static str, message: String },
+    IndexOutOfBounds { index: i64, length: usize },
+    SliceOutOfOrder { start: i64, end: i64 },
+    NotIndexable(&'static str),
+    MissingKey(Rc<str>),
+    InvalidIndex { container: &'static str, index: &'static str },
+    ReturnOutsideFunction,
+    StackOverflow { depth: usize },
+    LoopControlOutsideLoop { keyword: &'static str, label: Option<Rc<str>> },
+    NotIterable(&'static str),
+}
+
+impl Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeErrorKind::TypeMismatch { operation, left, right } => {
+                write!(f, "the operation {} is not defined for {} and {}", operation, left, right)
+            }
+            RuntimeErrorKind::InvalidOperand { operation, operand } => {
+                write!(f, "the operation {} is not defined for {}", operation, operand)
+            }
+            RuntimeErrorKind::NotABoolean { context, found } => write!(f, "expected a boolean {}, found {}", context, found),
+            RuntimeErrorKind::UndefinedVariable(name) => write!(f, "variable `{}` used without declaration", name),
+            RuntimeErrorKind::UndeclaredAssignment(name) => write!(f, "cannot assign to `{}`, which was never declared", name),
+            RuntimeErrorKind::ImmutableAssignment(name) => {
+                write!(f, "cannot assign to immutable variable `{}`; declare it with `let mut` to allow this", name)
+            }
+            RuntimeErrorKind::DivisionByZero => write!(f, "division by zero"),
+            RuntimeErrorKind::Overflow { operation } => write!(f, "integer overflow in {}", operation),
+            RuntimeErrorKind::NotCallable(type_name) => write!(f, "a value of type {} is not callable", type_name),
+            RuntimeErrorKind::ArgumentCountMismatch { function, expected, found } => {
+                write!(f, "`{}` takes {} argument(s) but {} were given", function, expected, found)
+            }
+            RuntimeErrorKind::InvalidArgument { function, message } => write!(f, "invalid argument to `{}`: {}", function, message),
+            RuntimeErrorKind::IndexOutOfBounds { index, length } => {
+                write!(f, "index {} is out of bounds for length {}", index, length)
+            }
+            RuntimeErrorKind::SliceOutOfOrder { start, end } => write!(f, "slice starts at {} but ends at {}", start, end),
+            RuntimeErrorKind::MissingKey(key) => write!(f, "key {:?} is not in the map", key),
+            RuntimeErrorKind::NotIndexable(type_name) => write!(f, "a value of type {} can't be indexed", type_name),
+            RuntimeErrorKind::InvalidIndex { container, index } => write!(f, "can't index into a {} with a value of type {}", container, index),
+            RuntimeErrorKind::ReturnOutsideFunction => write!(f, "`return` outside of a function"),
+            RuntimeErrorKind::StackOverflow { depth } => write!(f, "stack overflow: more than {} nested calls", depth),
+            RuntimeErrorKind::LoopControlOutsideLoop { keyword, label: None } => write!(f, "`{}` outside of a loop", keyword),
+            RuntimeErrorKind::LoopControlOutsideLoop { keyword, label: Some(label) } => {
+                write!(f, "`{} {}` is not inside a loop labeled `{}`", keyword, label, label)
+            }
+            RuntimeErrorKind::NotIterable(type_name) => write!(f, "a value of type {} can't be iterated over", type_name),
+        }
+    }
+}
+
+/// An error raised while evaluating a script, pointing at the expression that failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub span: Span,
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind, span: Span) -> RuntimeError {
+        RuntimeError { kind, span }
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span, self.kind)
+    }
+}
+
+/// Why evaluation of an expression or statement stopped early: either an
+/// error, a `return` travelling up to the function call that catches it, or a
+/// `break` / `continue` travelling up to the loop it names.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Unwind {
+    Error(RuntimeError),
+    Return(DataType, Span),
+    Break(Option<Rc<str>>, Span),
+    Continue(Option<Rc<str>>, Span),
+}
+
+impl Unwind {
+    /// The error to report when this unwind escapes the construct that should
+    /// have caught it, such as a `break` reaching the body of a function.
+    pub fn into_error(self) -> RuntimeError {
+        match self {
+            Unwind::Error(error) => error,
+            Unwind::Return(_, span) => RuntimeError::new(RuntimeErrorKind::ReturnOutsideFunction, span),
+            Unwind::Break(label, span) => {
+                RuntimeError::new(RuntimeErrorKind::LoopControlOutsideLoop { keyword: "break", label }, span)
+            }
+            Unwind::Continue(label, span) => {
+                RuntimeError::new(RuntimeErrorKind::LoopControlOutsideLoop { keyword: "continue", label }, span)
+            }
+        }
+    }
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Self {
+        Unwind::Error(error)
+    }
+}
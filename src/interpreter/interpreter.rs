@@ -0,0 +1,54 @@
+use std::rc::Rc;
+use crate::builtins::builtins::define_builtins;
+use crate::datatypes::datatypes::DataType;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::error::{RuntimeError, Unwind};
+use crate::parser::parser::Program;
+use crate::resolver::resolver::resolve;
+
+/// Walks a parsed `Program` statement by statement, keeping every binding made
+/// by a `let` in its global environment so later statements can read it.
+pub struct Interpreter {
+    env: Environment,
+}
+
+impl Interpreter {
+    /// Builtins live in a scope of their own, below the one the script's
+    /// globals go into, so a script can shadow them and `bindings` only shows
+    /// what the script defined.
+    pub fn new() -> Interpreter {
+        let mut env = Environment::new();
+        define_builtins(&mut env);
+        env.push_scope();
+        Interpreter { env }
+    }
+
+    /// Runs every statement of `program` in order, stopping at the first
+    /// runtime error. Bindings made before the error are kept.
+    ///
+    /// The program is resolved first, if it was not already, so locals are
+    /// always read from their slots and only globals are looked up by name.
+    pub fn run(&mut self, program: &Program) -> Result<(), RuntimeError> {
+        let mut program = program.clone();
+        resolve(&mut program);
+        for statement in program.statements() {
+            statement.run(&mut self.env).map_err(Unwind::into_error)?;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<DataType> {
+        self.env.lookup(name)
+    }
+
+    /// All global bindings made so far, sorted by name so the output is stable.
+    pub fn bindings(&self) -> Vec<(Rc<str>, DataType)> {
+        self.env.bindings()
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
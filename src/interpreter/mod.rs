@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod interpreter;
+pub mod environment;
+pub mod error;
\ No newline at end of file
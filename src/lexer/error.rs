@@ -0,0 +1,36 @@
+use std::fmt::{Display, Formatter};
+use crate::lexer::span::Span;
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum LexErrorKind {
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    UnterminatedComment,
+    InvalidEscape(String),
+}
+
+impl Display for LexErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LexErrorKind::UnexpectedCharacter(ch) if ch.is_control() => {
+                write!(f, "unexpected character `{}`", ch.escape_debug())
+            }
+            LexErrorKind::UnexpectedCharacter(ch) => write!(f, "unexpected character `{}`", ch),
+            LexErrorKind::UnterminatedString => write!(f, "unterminated string literal"),
+            LexErrorKind::UnterminatedComment => write!(f, "unterminated block comment"),
+            LexErrorKind::InvalidEscape(escape) => write!(f, "invalid escape sequence `{}`", escape),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span, self.kind)
+    }
+}
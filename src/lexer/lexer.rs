@@ -1,136 +1,397 @@
 use std::ops::Deref;
 use std::rc::Rc;
-use crate::lexer::token::Token;
+use crate::lexer::error::{LexError, LexErrorKind};
+use crate::lexer::span::Span;
+use crate::lexer::token::{SpannedToken, Token};
 
+/// Walks the input one `char` at a time. `position` is the byte offset of
+/// `ch` and `read_position` the byte offset of the char after it, so slicing
+/// the input between two positions is always on a char boundary.
 pub struct Lexer {
     input: Rc<str>,
     position: usize,
     read_position: usize,
     ch: char,
+    line: usize,
+    column: usize,
+    done: bool,
 }
 
 impl Lexer {
     pub fn new(input: &str) -> Lexer {
+        let first = input.chars().next();
+
         Lexer {
             input: Rc::from(input),
             position: 0,
-            read_position: 1,
-            ch: input.chars().nth(0).unwrap(),
+            read_position: first.map_or(0, char::len_utf8),
+            ch: first.unwrap_or('\0'),
+            line: 1,
+            column: 1,
+            done: false,
         }
     }
 
-    pub fn next(lexer: &mut Lexer) -> &mut Lexer {
-        if lexer.read_position >= lexer.input.len() {
-            lexer.ch = '\0';
+    pub fn advance(lexer: &mut Lexer) -> &mut Lexer {
+        if lexer.ch == '\n' {
+            lexer.line += 1;
+            lexer.column = 1;
         } else {
-            lexer.ch = lexer.input.chars().nth(lexer.read_position).unwrap()
+            lexer.column += 1;
         }
 
         lexer.position = lexer.read_position;
-        lexer.read_position += 1;
+
+        match lexer.input[lexer.position..].chars().next() {
+            Some(ch) => {
+                lexer.ch = ch;
+                lexer.read_position = lexer.position + ch.len_utf8();
+            }
+            None => { lexer.ch = '\0' }
+        }
 
         lexer
     }
 
 
     pub fn peek(&self) -> char {
-        if self.read_position >= self.input.len() {
-            return '\0'
-        } else {
-            return self.input.chars().nth(self.read_position).unwrap().clone();
-        }
+        self.input[self.read_position..].chars().next().unwrap_or('\0')
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        self.input[self.position..].starts_with(s)
+    }
+
+    /// `///` starts a doc comment, but four or more slashes are a plain comment.
+    fn at_doc_comment(&self) -> bool {
+        self.starts_with("///") && !self.starts_with("////")
     }
 }
 
 pub fn read_identifier(l: &mut Lexer) -> (Rc<str>, &mut Lexer) {
-    let position = l.position.clone();
+    let position = l.position;
 
     let mut l = l;
 
-    while l.ch.is_alphabetic() {
-        l = Lexer::next(l);
+    while l.ch.is_alphanumeric() || l.ch == '_' {
+        l = Lexer::advance(l);
     }
 
     let final_pos = l.position;
 
-    (Rc::from(&l.input.clone()[position..final_pos]), l)
+    (Rc::from(&l.input[position..final_pos]), l)
 }
 
+/// Reads an integer or a float. A `.` only belongs to the number when a digit
+/// follows it, so `0..10` is read as `0`, `..`, `10`.
 pub fn read_numerical(l: &mut Lexer) -> (Rc<str>, &mut Lexer) {
-    let position = l.position.clone();
+    let position = l.position;
 
     let mut l = l;
+    let mut seen_dot = false;
 
-    while l.ch.is_numeric() || l.ch == '.' {
-        l = Lexer::next(l);
+    while l.ch.is_ascii_digit() || (l.ch == '.' && !seen_dot && l.peek().is_ascii_digit()) {
+        seen_dot |= l.ch == '.';
+        l = Lexer::advance(l);
     }
 
     let final_pos = l.position;
 
-    (Rc::from(&l.input.clone()[position..final_pos]), l)
+    (Rc::from(&l.input[position..final_pos]), l)
 }
 
-pub fn next_token(l: &mut Lexer) -> (Token, &mut Lexer) {
-    let curr = l.ch;
+/// Reads a `"`-delimited string literal starting at the opening quote and
+/// decodes its escape sequences. A bad escape doesn't stop the scan, so the
+/// lexer still resumes after the closing quote.
+pub fn read_string(l: &mut Lexer) -> (Token, &mut Lexer) {
+    let mut l = Lexer::advance(l);
+    let mut value = String::new();
+    let mut error: Option<LexErrorKind> = None;
+
+    loop {
+        match l.ch {
+            '"' => {
+                l = Lexer::advance(l);
+                break;
+            }
+            '\0' if l.position >= l.input.len() => {
+                return (Token::ILLEGAL(LexErrorKind::UnterminatedString), l)
+            }
+            '\\' => {
+                l = Lexer::advance(l);
+                let escaped = match l.ch {
+                    'n' => Some('\n'),
+                    't' => Some('\t'),
+                    'r' => Some('\r'),
+                    '0' => Some('\0'),
+                    '"' => Some('"'),
+                    '\\' => Some('\\'),
+                    'u' => {
+                        let (escaped, next) = read_unicode_escape(l);
+                        l = next;
+                        match escaped {
+                            Ok(ch) => Some(ch),
+                            Err(kind) => {
+                                error.get_or_insert(kind);
+                                None
+                            }
+                        }
+                    }
+                    '\0' if l.position >= l.input.len() => {
+                        return (Token::ILLEGAL(LexErrorKind::UnterminatedString), l)
+                    }
+                    other => {
+                        error.get_or_insert(LexErrorKind::InvalidEscape(format!("\\{}", other)));
+                        None
+                    }
+                };
+
+                if let Some(ch) = escaped {
+                    value.push(ch);
+                }
+                l = Lexer::advance(l);
+            }
+            ch => {
+                value.push(ch);
+                l = Lexer::advance(l);
+            }
+        }
+    }
+
+    match error {
+        Some(kind) => (Token::ILLEGAL(kind), l),
+        None => (Token::String(Rc::from(value)), l)
+    }
+}
+
+/// Reads the `u{...}` part of a `\u{...}` escape, leaving the lexer on the closing brace.
+fn read_unicode_escape(l: &mut Lexer) -> (Result<char, LexErrorKind>, &mut Lexer) {
+    let start = l.position - 1;
+    let mut l = l;
+
+    if l.peek() != '{' {
+        return (Err(LexErrorKind::InvalidEscape(String::from("\\u"))), l)
+    }
+
+    l = Lexer::advance(l);
+    let digits_start = l.read_position;
+
+    while l.peek().is_ascii_hexdigit() {
+        l = Lexer::advance(l);
+    }
+
+    if l.peek() != '}' {
+        let end = l.read_position;
+        return (Err(LexErrorKind::InvalidEscape(String::from(&l.input[start..end]))), l)
+    }
+
+    let digits_end = l.read_position;
+    l = Lexer::advance(l);
+    let digits = &l.input[digits_start..digits_end];
+    let escape = String::from(&l.input[start..l.read_position]);
+
+    let ch = u32::from_str_radix(digits, 16).ok()
+        .filter(|_| digits.len() <= 6)
+        .and_then(char::from_u32);
+
+    match ch {
+        Some(ch) => (Ok(ch), l),
+        None => (Err(LexErrorKind::InvalidEscape(escape)), l)
+    }
+}
+
+/// Reads a `///` comment up to the end of the line. The slashes and a single
+/// space after them are not part of the text.
+fn read_doc_comment(l: &mut Lexer) -> (Token, &mut Lexer) {
+    let mut l = Lexer::advance(Lexer::advance(Lexer::advance(l)));
+
+    if l.ch == ' ' {
+        l = Lexer::advance(l);
+    }
+
+    let start = l.position;
+
+    while l.ch != '\n' && l.position < l.input.len() {
+        l = Lexer::advance(l);
+    }
+
+    let text = l.input[start..l.position].trim_end_matches('\r');
+    (Token::DocComment(Rc::from(text)), l)
+}
+
+fn skip_whitespace(l: &mut Lexer) -> &mut Lexer {
+    let mut l = l;
+
+    while l.ch == ' ' || l.ch == '\r' || l.ch == '\t' || l.ch == '\n' {
+        l = Lexer::advance(l);
+    }
+
+    l
+}
+
+/// Skips a `//` comment up to the end of the line, or a `/* */` comment
+/// including any comments nested inside it. Returns `false` if a block comment
+/// runs off the end of the input.
+fn skip_comment(l: &mut Lexer) -> (bool, &mut Lexer) {
+    let mut l = l;
+
+    if l.starts_with("//") {
+        while l.ch != '\n' && l.position < l.input.len() {
+            l = Lexer::advance(l);
+        }
+        return (true, l)
+    }
+
+    let mut depth = 0;
+
+    loop {
+        if l.starts_with("/*") {
+            depth += 1;
+            l = Lexer::advance(Lexer::advance(l));
+        } else if l.starts_with("*/") {
+            depth -= 1;
+            l = Lexer::advance(Lexer::advance(l));
+            if depth == 0 {
+                return (true, l)
+            }
+        } else if l.position >= l.input.len() {
+            return (false, l)
+        } else {
+            l = Lexer::advance(l);
+        }
+    }
+}
 
-    if curr == ' ' ||  curr == '\r' || curr == '\t' || curr == '\n' {
-        let l = Lexer::next(l);
-        return next_token(l);
+pub fn next_token(l: &mut Lexer) -> (SpannedToken, &mut Lexer) {
+    let mut l = skip_whitespace(l);
+
+    while (l.starts_with("//") && !l.at_doc_comment()) || l.starts_with("/*") {
+        let (start, line, column) = (l.position, l.line, l.column);
+        let (closed, next) = skip_comment(l);
+        l = next;
+
+        if !closed {
+            let span = Span::new(start, l.position, line, column);
+            return (SpannedToken { token: Token::ILLEGAL(LexErrorKind::UnterminatedComment), span }, l)
+        }
+
+        l = skip_whitespace(l);
     }
 
-    else if curr.is_alphabetic() {
+    let (start, line, column) = (l.position, l.line, l.column);
+
+    let (token, l) = read_token(l);
+
+    let end = if token == Token::EOF { start } else { l.position };
+
+    (SpannedToken { token, span: Span::new(start, end, line, column) }, l)
+}
+
+fn read_token(l: &mut Lexer) -> (Token, &mut Lexer) {
+    let curr = l.ch;
+
+    if curr.is_alphabetic() || curr == '_' {
         let token_res = read_identifier(l);
 
-        let token_raw = Rc::clone(&token_res.0).clone();
+        let token_raw = Rc::clone(&token_res.0);
 
         let token = match token_raw.deref() {
             "let" => Token::LET,
+            "mut" => Token::MUT,
             "fn" => Token::FUNCTION,
             "return" => Token::RETURN,
             "if" => Token::IF,
             "else" => Token::ELSE,
             "true" => Token::TRUE,
             "false" => Token::FALSE,
+            "while" => Token::WHILE,
+            "for" => Token::FOR,
+            "in" => Token::IN,
+            "break" => Token::BREAK,
+            "continue" => Token::CONTINUE,
             _ => Token::IDENTIFIER(Rc::clone(&token_raw))
         };
 
         return (token, token_res.1)
     }
 
-    else if curr.is_numeric() {
+    else if curr.is_ascii_digit() {
         let token_res = read_numerical(l);
 
-        let token_raw = Rc::clone(&token_res.0).clone();
+        let token_raw = Rc::clone(&token_res.0);
 
         let token = Token::Number(Rc::clone(&token_raw));
 
         return (token, token_res.1)
     }
 
+    else if curr == '"' {
+        return read_string(l)
+    }
+
+    else if l.at_doc_comment() {
+        return read_doc_comment(l)
+    }
+
     else if curr == '=' {
         let nex = l.peek();
 
         if nex == '=' {
-            let l = Lexer::next(l);
-            let l = Lexer::next(l);
+            let l = Lexer::advance(l);
+            let l = Lexer::advance(l);
             return (Token::EQAULITY, l)
         } else {
-            let l = Lexer::next(l);
-            let l = Lexer::next(l);
+            let l = Lexer::advance(l);
             return (Token::ASSIGN, l)
         }
     }
 
+    else if matches!(curr, '+' | '-' | '*' | '/' | '%') && l.peek() == '=' {
+        let l = Lexer::advance(l);
+        let l = Lexer::advance(l);
+        let token = match curr {
+            '+' => Token::ADDASSIGN,
+            '-' => Token::SUBTRACTASSIGN,
+            '*' => Token::MULTIPLICATIONASSIGN,
+            '/' => Token::DIVISIONASSIGN,
+            _ => Token::MODULOASSIGN,
+        };
+        return (token, l)
+    }
+
+    else if curr == '<' || curr == '>' {
+        let nex = l.peek();
+
+        if nex == '=' {
+            let l = Lexer::advance(l);
+            let l = Lexer::advance(l);
+            let token = if curr == '<' { Token::LESSTHANEQUAL } else { Token::GREATERTHANEQUAL };
+            return (token, l)
+        }
+    }
+
+    else if (curr == '&' || curr == '|') && l.peek() == curr {
+        let l = Lexer::advance(l);
+        let l = Lexer::advance(l);
+        let token = if curr == '&' { Token::AND } else { Token::OR };
+        return (token, l)
+    }
+
+    else if curr == '.' && l.peek() == '.' {
+        let l = Lexer::advance(l);
+        let l = Lexer::advance(l);
+        return (Token::DOTDOT, l)
+    }
+
     else if curr == '!' {
         let nex = l.peek();
 
         if nex == '=' {
-            let l = Lexer::next(l);
-            let l = Lexer::next(l);
+            let l = Lexer::advance(l);
+            let l = Lexer::advance(l);
             return (Token::NOTEQUALITY, l)
         } else {
-            let l = Lexer::next(l);
-            let l = Lexer::next(l);
+            let l = Lexer::advance(l);
             return (Token::BANG, l)
         }
     }
@@ -140,19 +401,55 @@ pub fn next_token(l: &mut Lexer) -> (Token, &mut Lexer) {
         '-' => Token::SUBTRACT,
         '*' => Token::MULTIPLICATION,
         '/' => Token::DIVISION,
+        '%' => Token::MODULO,
         '<' => Token::LESSTHAN,
         '>' => Token::GREATERTHAN,
         ',' => Token::COMMA,
+        ':' => Token::COLON,
+        '.' => Token::DOT,
         '(' => Token::LPAREN,
         ')' => Token::RPAREN,
         '{' => Token::LBRACE,
         '}' => Token::RBRACE,
+        '[' => Token::LBRACKET,
+        ']' => Token::RBRACKET,
         ';' => Token::SEMICOLON,
-        '\0' => Token::EOF,
-        _ => Token::ILLEGAL
+        // A NUL byte in the middle of the input is not the end of it.
+        '\0' if l.position >= l.input.len() => return (Token::EOF, l),
+        _ => Token::ILLEGAL(LexErrorKind::UnexpectedCharacter(curr))
     };
 
-    let l = Lexer::next(l);
+    let l = Lexer::advance(l);
 
     (token, l)
-}
\ No newline at end of file
+}
+
+/// Yields every token of the input, ending with a single `Token::EOF`.
+impl Iterator for Lexer {
+    type Item = SpannedToken;
+
+    fn next(&mut self) -> Option<SpannedToken> {
+        if self.done {
+            return None
+        }
+
+        let (token, _) = next_token(self);
+        self.done = token.token == Token::EOF;
+        Some(token)
+    }
+}
+
+/// Lexes the whole of `input` up front, failing on the first character that
+/// can't start a token. The returned tokens always end with `Token::EOF`.
+pub fn tokenize(input: &str) -> Result<Vec<SpannedToken>, LexError> {
+    let mut tokens = vec![];
+
+    for token in Lexer::new(input) {
+        if let Token::ILLEGAL(kind) = token.token {
+            return Err(LexError { kind, span: token.span })
+        }
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
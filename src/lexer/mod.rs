@@ -1,2 +1,5 @@
 pub mod token;
-pub mod lexer;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod lexer;
+pub mod span;
+pub mod error;
\ No newline at end of file
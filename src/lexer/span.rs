@@ -0,0 +1,28 @@
+use std::fmt::{Display, Formatter};
+
+/// A region of the source text. `start` and `end` are offsets into the input,
+/// `line` and `column` are the 1-based position of `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Span {
+        Span { start, end, line, column }
+    }
+
+    /// The smallest span covering both `self` and `other`, where `other` comes later in the source.
+    pub fn to(&self, other: Span) -> Span {
+        Span { start: self.start, end: other.end.max(self.end), line: self.line, column: self.column }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
@@ -1,19 +1,34 @@
 use std::rc::Rc;
+use std::fmt::{Display, Formatter};
+use crate::lexer::error::LexErrorKind;
+use crate::lexer::span::Span;
 
 #[derive(Debug, PartialOrd, PartialEq, Clone)]
 pub enum Token {
     IDENTIFIER(Rc<str>),
     Number(Rc<str>),
-    ILLEGAL,
+    String(Rc<str>),
+    DocComment(Rc<str>),
+    ILLEGAL(LexErrorKind),
     EOF,
     ASSIGN,
     ADD,
     SUBTRACT,
     MULTIPLICATION,
     DIVISION,
+    MODULO,
+    ADDASSIGN,
+    SUBTRACTASSIGN,
+    MULTIPLICATIONASSIGN,
+    DIVISIONASSIGN,
+    MODULOASSIGN,
     LESSTHAN,
     GREATERTHAN,
+    LESSTHANEQUAL,
+    GREATERTHANEQUAL,
     BANG,
+    AND,
+    OR,
     EQAULITY,
     NOTEQUALITY,
     IF,
@@ -22,11 +37,85 @@ pub enum Token {
     TRUE,
     FALSE,
     SEMICOLON,
+    COLON,
     COMMA,
+    DOT,
+    DOTDOT,
     LPAREN,
     RPAREN,
     LBRACE,
     RBRACE,
+    LBRACKET,
+    RBRACKET,
     FUNCTION,
-    LET
+    LET,
+    MUT,
+    WHILE,
+    FOR,
+    IN,
+    BREAK,
+    CONTINUE
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::IDENTIFIER(name) => write!(f, "identifier `{}`", name),
+            Token::Number(val) => write!(f, "number `{}`", val),
+            Token::String(val) => write!(f, "string {:?}", val),
+            Token::DocComment(_) => write!(f, "doc comment"),
+            Token::ILLEGAL(kind) => write!(f, "{}", kind),
+            Token::EOF => write!(f, "end of file"),
+            Token::ASSIGN => write!(f, "`=`"),
+            Token::ADD => write!(f, "`+`"),
+            Token::SUBTRACT => write!(f, "`-`"),
+            Token::MULTIPLICATION => write!(f, "`*`"),
+            Token::DIVISION => write!(f, "`/`"),
+            Token::MODULO => write!(f, "`%`"),
+            Token::ADDASSIGN => write!(f, "`+=`"),
+            Token::SUBTRACTASSIGN => write!(f, "`-=`"),
+            Token::MULTIPLICATIONASSIGN => write!(f, "`*=`"),
+            Token::DIVISIONASSIGN => write!(f, "`/=`"),
+            Token::MODULOASSIGN => write!(f, "`%=`"),
+            Token::LESSTHAN => write!(f, "`<`"),
+            Token::GREATERTHAN => write!(f, "`>`"),
+            Token::LESSTHANEQUAL => write!(f, "`<=`"),
+            Token::GREATERTHANEQUAL => write!(f, "`>=`"),
+            Token::BANG => write!(f, "`!`"),
+            Token::AND => write!(f, "`&&`"),
+            Token::OR => write!(f, "`||`"),
+            Token::EQAULITY => write!(f, "`==`"),
+            Token::NOTEQUALITY => write!(f, "`!=`"),
+            Token::IF => write!(f, "`if`"),
+            Token::ELSE => write!(f, "`else`"),
+            Token::RETURN => write!(f, "`return`"),
+            Token::TRUE => write!(f, "`true`"),
+            Token::FALSE => write!(f, "`false`"),
+            Token::SEMICOLON => write!(f, "`;`"),
+            Token::COLON => write!(f, "`:`"),
+            Token::COMMA => write!(f, "`,`"),
+            Token::DOT => write!(f, "`.`"),
+            Token::DOTDOT => write!(f, "`..`"),
+            Token::LPAREN => write!(f, "`(`"),
+            Token::RPAREN => write!(f, "`)`"),
+            Token::LBRACE => write!(f, "`{{`"),
+            Token::RBRACE => write!(f, "`}}`"),
+            Token::LBRACKET => write!(f, "`[`"),
+            Token::RBRACKET => write!(f, "`]`"),
+            Token::FUNCTION => write!(f, "`fn`"),
+            Token::LET => write!(f, "`let`"),
+            Token::MUT => write!(f, "`mut`"),
+            Token::WHILE => write!(f, "`while`"),
+            Token::FOR => write!(f, "`for`"),
+            Token::IN => write!(f, "`in`"),
+            Token::BREAK => write!(f, "`break`"),
+            Token::CONTINUE => write!(f, "`continue`"),
+        }
+    }
 }
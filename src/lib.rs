@@ -0,0 +1,11 @@
+
+pub mod lexer;
+pub mod parser;
+pub mod datatypes;
+pub mod operators;
+pub mod interpreter;
+pub mod builtins;
+pub mod resolver;
+pub mod optimizer;
+pub mod compiler;
+pub mod vm;
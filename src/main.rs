@@ -1,33 +1,118 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
 use std::rc::Rc;
-use crate::lexer::lexer::{Lexer, next_token};
-use crate::lexer::token::Token;
-use crate::parser::parser::{parse, Parser};
+use std::thread;
+use scriptx::compiler::binary::MAGIC;
+use scriptx::compiler::bytecode::Bytecode;
+use scriptx::compiler::compiler::compile;
+use scriptx::datatypes::function::STACK_SIZE;
+use scriptx::interpreter::interpreter::Interpreter;
+use scriptx::lexer::lexer::Lexer;
+use scriptx::optimizer::optimizer::optimize;
+use scriptx::parser::parser::{parse, Parser, Program};
+use scriptx::resolver::resolver::resolve;
+use scriptx::vm::vm::Vm;
 
-mod lexer;
-mod parser;
-mod datatypes;
-mod operators;
+const USAGE: &str = "usage: scriptx [file]\n       scriptx compile <file> [-o <output>]";
 
 fn main() {
-    let mut lexer = Lexer::new("
+    let args: Vec<String> = env::args().skip(1).collect();
+    // Scripts may recurse as deep as `MAX_CALL_DEPTH`, which needs more than
+    // the main thread's stack.
+    let script = thread::Builder::new().stack_size(STACK_SIZE).spawn(move || {
+        match args.first().map(String::as_str) {
+            Some("compile") => compile_file(&args[1..]),
+            Some(path) => run_file(path),
+            None => run_source("
     let foo = 5 * (10 + 2);
-    ");
+    "),
+        }
+    });
+    if script.unwrap_or_else(|e| fail(format!("could not start the script thread: {}", e))).join().is_err() {
+        process::exit(101);
+    }
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
+}
 
-    let mut tokens: Vec<Token> = vec![];
+fn read(path: &str) -> Vec<u8> {
+    fs::read(path).unwrap_or_else(|e| fail(format!("could not read {}: {}", path, e)))
+}
 
-    let mut token = next_token(&mut lexer);
+/// Parses, resolves and optimizes a script, reporting warnings and stopping on errors.
+fn parse_source(source: &str) -> Program {
+    let mut parser = Parser::new(Lexer::new(source));
+    let mut program = match parse(&mut parser) {
+        Ok(program) => program,
+        Err(errors) => {
+            for error in errors {
+                eprintln!("error: {}", error);
+            }
+            process::exit(1);
+        }
+    };
 
-    while token.0 != Token::EOF {
-        tokens.push(token.0);
-        let lexer = token.1;
-        token = next_token(lexer)
+    let diagnostics = resolve(&mut program);
+    for diagnostic in &diagnostics {
+        eprintln!("{}: {}", if diagnostic.is_error() { "error" } else { "warning" }, diagnostic);
     }
+    if diagnostics.iter().any(|diagnostic| diagnostic.is_error()) {
+        process::exit(1);
+    }
+    optimize(&mut program);
+    program
+}
 
-    tokens.push(Token::EOF);
+/// Runs a script with the interpreter, or a file written by `scriptx compile`
+/// with the virtual machine.
+fn run_file(path: &str) {
+    let bytes = read(path);
+    if !bytes.starts_with(MAGIC) {
+        let source = String::from_utf8(bytes).unwrap_or_else(|_| fail(format!("{} is not UTF-8", path)));
+        return run_source(&source);
+    }
 
-    let tokens: Rc<[Token]> = Rc::from(tokens.into_boxed_slice());
+    let bytecode = Bytecode::from_bytes(&bytes).unwrap_or_else(|e| fail(format!("could not load {}: {}", path, e)));
+    let mut vm = Vm::new();
+    if let Err(error) = vm.run(&Rc::new(bytecode)) {
+        fail(error);
+    }
+
+    for (identifier, value) in vm.bindings() {
+        println!("{} = {:?}", identifier, value);
+    }
+}
 
-    let mut parser = Parser::new(tokens);
+fn run_source(source: &str) {
+    let program = parse_source(source);
 
-    println!("{:?}", parse(&mut parser))
-}
\ No newline at end of file
+    let mut interpreter = Interpreter::new();
+    if let Err(error) = interpreter.run(&program) {
+        fail(error);
+    }
+
+    for (identifier, value) in interpreter.bindings() {
+        println!("{} = {:?}", identifier, value);
+    }
+}
+
+/// `scriptx compile foo.sx -o foo.sxc`; the output defaults to the input
+/// with an `.sxc` extension.
+fn compile_file(args: &[String]) {
+    let (input, output) = match args {
+        [input] => (input, Path::new(input).with_extension("sxc")),
+        [input, flag, output] if flag == "-o" => (input, Path::new(output).to_path_buf()),
+        _ => fail(USAGE),
+    };
+
+    let source = String::from_utf8(read(input)).unwrap_or_else(|_| fail(format!("{} is not UTF-8", input)));
+    let bytecode = compile(&parse_source(&source));
+    if let Err(e) = fs::write(&output, bytecode.to_bytes()) {
+        fail(format!("could not write {}: {}", output.display(), e));
+    }
+}
@@ -1,44 +1,153 @@
+use std::cmp::Ordering;
+use crate::interpreter::error::RuntimeErrorKind;
 use crate::lexer::token::Token;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BinaryOperator {
     Add,
     Subtract,
     Multiply,
     Divide,
+    Remainder,
     Or,
-    And
+    And,
+    Equal,
+    NotEqual,
+    LessThan,
+    GreaterThan,
+    LessThanOrEqual,
+    GreaterThanOrEqual
+}
+
+/// How tightly an operator binds its operands, from loosest to tightest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Precedence {
+    Lowest,
+    Or,
+    And,
+    Equality,
+    Comparison,
+    Sum,
+    Product,
+    Prefix
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right
 }
 
 impl BinaryOperator {
-    pub fn from_token(t: &Token) -> BinaryOperator {
-        return match t {
-            &Token::ADD => BinaryOperator::Add,
-            &Token::SUBTRACT => BinaryOperator::Subtract,
-            &Token::MULTIPLICATION => BinaryOperator::Multiply,
-            &Token::DIVISION => BinaryOperator::Divide,
-            _ => {panic!("Invalid operator token")}
+    /// The infix operator a token stands for, if any. New infix operators are
+    /// added here and in `precedence`; the parser picks them up from there.
+    pub fn from_token(t: &Token) -> Option<BinaryOperator> {
+        match *t {
+            Token::ADD => Some(BinaryOperator::Add),
+            Token::SUBTRACT => Some(BinaryOperator::Subtract),
+            Token::MULTIPLICATION => Some(BinaryOperator::Multiply),
+            Token::DIVISION => Some(BinaryOperator::Divide),
+            Token::MODULO => Some(BinaryOperator::Remainder),
+            Token::AND => Some(BinaryOperator::And),
+            Token::OR => Some(BinaryOperator::Or),
+            Token::EQAULITY => Some(BinaryOperator::Equal),
+            Token::NOTEQUALITY => Some(BinaryOperator::NotEqual),
+            Token::LESSTHAN => Some(BinaryOperator::LessThan),
+            Token::GREATERTHAN => Some(BinaryOperator::GreaterThan),
+            Token::LESSTHANEQUAL => Some(BinaryOperator::LessThanOrEqual),
+            Token::GREATERTHANEQUAL => Some(BinaryOperator::GreaterThanOrEqual),
+            _ => None
+        }
+    }
+
+    pub fn precedence(&self) -> Precedence {
+        match self {
+            BinaryOperator::Or => Precedence::Or,
+            BinaryOperator::And => Precedence::And,
+            BinaryOperator::Equal | BinaryOperator::NotEqual => Precedence::Equality,
+            BinaryOperator::LessThan | BinaryOperator::GreaterThan
+            | BinaryOperator::LessThanOrEqual | BinaryOperator::GreaterThanOrEqual => Precedence::Comparison,
+            BinaryOperator::Add | BinaryOperator::Subtract => Precedence::Sum,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Remainder => Precedence::Product,
         }
     }
+
+    /// The operator a compound assignment such as `+=` applies before storing
+    /// the result, if `t` is one.
+    pub fn from_assignment_token(t: &Token) -> Option<BinaryOperator> {
+        match *t {
+            Token::ADDASSIGN => Some(BinaryOperator::Add),
+            Token::SUBTRACTASSIGN => Some(BinaryOperator::Subtract),
+            Token::MULTIPLICATIONASSIGN => Some(BinaryOperator::Multiply),
+            Token::DIVISIONASSIGN => Some(BinaryOperator::Divide),
+            Token::MODULOASSIGN => Some(BinaryOperator::Remainder),
+            _ => None
+        }
+    }
+
+    pub fn associativity(&self) -> Associativity {
+        Associativity::Left
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UnaryOperator {
-    Not
+    Not,
+    Negate,
+    Plus
+}
+
+impl UnaryOperator {
+    /// The prefix operator a token stands for, if any. New prefix operators
+    /// are added here; they all bind at `Precedence::Prefix`.
+    pub fn from_token(t: &Token) -> Option<UnaryOperator> {
+        match *t {
+            Token::BANG => Some(UnaryOperator::Not),
+            Token::SUBTRACT => Some(UnaryOperator::Negate),
+            Token::ADD => Some(UnaryOperator::Plus),
+            _ => None
+        }
+    }
+}
+
+pub trait AddOperatorTrait: Sized {
+    fn add(&self, other: Self) -> Result<Self, RuntimeErrorKind>;
+}
+
+pub trait SubtractOperatorTrait: Sized {
+    fn subtract(&self, other: Self) -> Result<Self, RuntimeErrorKind>;
+}
+
+pub trait MultiplyOperatorTrait: Sized {
+    fn multiply(&self, other: Self) -> Result<Self, RuntimeErrorKind>;
+}
+
+pub trait DivideOperatorTrait: Sized {
+    fn divide(&self, other: Self) -> Result<Self, RuntimeErrorKind>;
+}
+
+pub trait RemainderOperatorTrait: Sized {
+    fn remainder(&self, other: Self) -> Result<Self, RuntimeErrorKind>;
+}
+
+pub trait NotOperatorTrait: Sized {
+    fn not(&self) -> Result<Self, RuntimeErrorKind>;
 }
 
-pub trait AddOperatorTrait {
-    fn add(&self, other: Self) -> Self;
+pub trait NegateOperatorTrait: Sized {
+    fn negate(&self) -> Result<Self, RuntimeErrorKind>;
 }
 
-pub trait SubtractOperatorTrait {
-    fn subtract(&self, other: Self) -> Self;
+pub trait PlusOperatorTrait: Sized {
+    fn plus(&self) -> Result<Self, RuntimeErrorKind>;
 }
 
-pub trait MultiplyOperatorTrait {
-    fn multiply(&self, other: Self) -> Self;
+pub trait EqualityOperatorTrait {
+    fn equals(&self, other: &Self) -> bool;
 }
 
-pub trait DivideOperatorTrait {
-    fn divide(&self, other: Self) -> Self;
+/// Ordering between two values. `Ok(None)` means both values are of an
+/// ordered type but are unordered with respect to each other, as with NaN.
+pub trait ComparisonOperatorTrait {
+    fn compare(&self, other: &Self) -> Result<Option<Ordering>, RuntimeErrorKind>;
 }
@@ -0,0 +1,89 @@
+use std::mem;
+use crate::datatypes::datatypes::DataType;
+use crate::lexer::span::Span;
+use crate::operators::operators::BinaryOperator;
+use crate::parser::expression::{Block, Expr, Literal};
+use crate::parser::parser::Program;
+use crate::parser::statement::Stmt;
+use crate::parser::visitor::{walk_block_mut, walk_expr_mut, VisitorMut};
+
+/// Simplifies `program` without changing what it does:
+///
+/// - operators whose operands are all literals are replaced by their result,
+///   worked out with the same `DataType` operations the interpreter uses. An
+///   operation that would fail, like `1 / 0`, is left to fail at runtime.
+/// - an `if` with a literal `true` or `false` condition is replaced by the
+///   branch that would run.
+/// - statements of a block after a `return`, `break` or `continue`, which
+///   can never run, are removed.
+///
+/// Scopes are left as they are, so the pass can run after `resolve`.
+pub fn optimize(program: &mut Program) {
+    Optimizer.visit_program_mut(program);
+}
+
+struct Optimizer;
+
+impl VisitorMut for Optimizer {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr);
+        let placeholder = literal(DataType::Nil, expr.span());
+        *expr = fold(mem::replace(expr, placeholder));
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        walk_block_mut(self, block);
+        let unwinds = |statement: &Stmt| matches!(statement, Stmt::Return(_) | Stmt::Break(_) | Stmt::Continue(_));
+        if let Some(last) = block.statements.iter().position(unwinds) {
+            block.statements.truncate(last + 1);
+            block.value = None;
+        }
+    }
+}
+
+fn literal(value: DataType, span: Span) -> Expr {
+    Expr::Literal(Literal { value, span })
+}
+
+/// The simpler expression `expr` can be replaced by, or `expr` itself.
+/// Its operands have already been folded.
+fn fold(expr: Expr) -> Expr {
+    match expr {
+        Expr::Binary(e) => {
+            let Expr::Literal(l) = &*e.l else { return Expr::Binary(e) };
+            let folded = match (e.op, &l.value, &*e.r) {
+                // The right operand never runs.
+                (BinaryOperator::Or, DataType::Boolean(true), _) | (BinaryOperator::And, DataType::Boolean(false), _) => {
+                    Some(l.value.clone())
+                }
+                (BinaryOperator::Or | BinaryOperator::And, DataType::Boolean(_), Expr::Literal(r)) => match r.value {
+                    DataType::Boolean(_) => Some(r.value.clone()),
+                    _ => None
+                },
+                (BinaryOperator::Or | BinaryOperator::And, _, _) => None,
+                (op, left, Expr::Literal(r)) => left.binary_operation(op, r.value.clone()).ok(),
+                _ => None
+            };
+            match folded {
+                Some(value) => literal(value, e.span),
+                None => Expr::Binary(e)
+            }
+        }
+        Expr::Unary(e) => match &*e.inp {
+            Expr::Literal(operand) => match operand.value.unary_operation(e.op) {
+                Ok(value) => literal(value, e.span),
+                Err(_) => Expr::Unary(e)
+            },
+            _ => Expr::Unary(e)
+        },
+        Expr::If(e) => match &*e.condition {
+            Expr::Literal(Literal { value: DataType::Boolean(true), .. }) => Expr::Block(e.consequence),
+            Expr::Literal(Literal { value: DataType::Boolean(false), .. }) => match e.alternative {
+                Some(alternative) => *alternative,
+                None => Expr::Block(Block { statements: vec![], value: None, span: e.span })
+            },
+            _ => Expr::If(e)
+        },
+        other => other
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt::{Display, Formatter};
+use crate::lexer::span::Span;
+use crate::lexer::token::Token;
+
+/// Something the parser would have accepted at the point where it gave up.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expected {
+    Token(Token),
+    Identifier,
+    Expression,
+    Statement,
+    MapKey,
+    IntegerInRange,
+    FiniteFloat,
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expected::Token(t) => write!(f, "{}", t),
+            Expected::Identifier => write!(f, "an identifier"),
+            Expected::Expression => write!(f, "an expression"),
+            Expected::Statement => write!(f, "a statement"),
+            Expected::MapKey => write!(f, "a string key"),
+            Expected::IntegerInRange => write!(f, "an integer no greater than {}", i64::MAX),
+            Expected::FiniteFloat => write!(f, "a float within the range of 64-bit floats"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    /// `found` is none of the things the parser would have accepted.
+    Unexpected { expected: Vec<Expected> },
+    /// More than `MAX_NESTING` expressions or blocks inside each other.
+    TooDeep,
+    /// A doc comment in front of `found`, as only a `let` can be documented.
+    DanglingDoc,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub found: Token,
+    pub span: Span,
+}
+
+impl ParseError {
+    pub fn new(expected: Vec<Expected>, found: &Token, span: Span) -> ParseError {
+        ParseError::of(ParseErrorKind::Unexpected { expected }, found, span)
+    }
+
+    pub fn of(kind: ParseErrorKind, found: &Token, span: Span) -> ParseError {
+        ParseError { kind, found: found.clone(), span }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let expected = match &self.kind {
+            ParseErrorKind::Unexpected { expected } => expected,
+            ParseErrorKind::TooDeep => return write!(f, "{}: expression nested too deeply", self.span),
+            ParseErrorKind::DanglingDoc => {
+                return write!(f, "{}: doc comment before {} instead of a `let`", self.span, self.found)
+            }
+        };
+        write!(f, "{}: expected ", self.span)?;
+        for (i, item) in expected.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", if i + 1 == expected.len() { " or " } else { ", " })?;
+            }
+            write!(f, "{}", item)?;
+        }
+        write!(f, ", found {}", self.found)
+    }
+}
@@ -1,76 +1,339 @@
-use std::collections::HashMap;
-use std::fmt::Debug;
+use std::rc::Rc;
 use crate::datatypes::datatypes::DataType;
-use crate::operators::operators::{AddOperatorTrait, BinaryOperator, DivideOperatorTrait, MultiplyOperatorTrait, SubtractOperatorTrait, UnaryOperator};
+use crate::datatypes::function::Function;
+use crate::datatypes::map::Map;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::error::{RuntimeError, RuntimeErrorKind, Unwind};
+use crate::lexer::span::Span;
+use crate::operators::operators::{BinaryOperator, UnaryOperator};
 use crate::parser::identifier::Identifier;
+use crate::parser::statement::Stmt;
 
-pub trait ExpressionLiteral: Debug {
-    fn value(&self, mem_table: &HashMap<Identifier, Box<DataType>>) -> DataType;
+/// A node of the expression tree. Every variant is evaluated by the node it
+/// wraps; tools that only need to walk the tree can use the visitors in
+/// `parser::visitor` instead of matching on it by hand.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Literal(Literal),
+    Identifier(Identifier),
+    Binary(BinaryOperatorExpression),
+    Unary(UnaryOperatorExpression),
+    Call(CallExpression),
+    If(IfExpression),
+    Function(Rc<FunctionLiteral>),
+    Block(Block),
+    List(ListLiteral),
+    Index(IndexExpression),
+    Slice(SliceExpression),
+    Map(MapLiteral),
+    Field(FieldExpression)
 }
 
-#[derive(Debug)]
+impl Expr {
+    pub fn value(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        match self {
+            Expr::Literal(v) => v.value(env),
+            Expr::Identifier(v) => v.value(env),
+            Expr::Binary(v) => v.value(env),
+            Expr::Unary(v) => v.value(env),
+            Expr::Call(v) => v.value(env),
+            Expr::If(v) => v.value(env),
+            Expr::Function(v) => Ok(FunctionLiteral::value(v, env)),
+            Expr::Block(v) => v.value(env),
+            Expr::List(v) => v.value(env),
+            Expr::Index(v) => v.value(env),
+            Expr::Slice(v) => v.value(env),
+            Expr::Map(v) => v.value(env),
+            Expr::Field(v) => v.value(env),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Literal(v) => v.span,
+            Expr::Identifier(v) => v.span,
+            Expr::Binary(v) => v.span,
+            Expr::Unary(v) => v.span,
+            Expr::Call(v) => v.span,
+            Expr::If(v) => v.span,
+            Expr::Function(v) => v.span,
+            Expr::Block(v) => v.span,
+            Expr::List(v) => v.span,
+            Expr::Index(v) => v.span,
+            Expr::Slice(v) => v.span,
+            Expr::Map(v) => v.span,
+            Expr::Field(v) => v.span,
+        }
+    }
+
+    /// Whether the expression ends in a `}` of its own, like `if` and blocks
+    /// do. Such an expression can stand as a statement without a `;`.
+    pub fn ends_with_block(&self) -> bool {
+        matches!(self, Expr::If(_) | Expr::Block(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Literal {
+    pub value: DataType,
+    pub span: Span
+}
+
+impl Literal {
+    pub fn value(&self, _env: &mut Environment) -> Result<DataType, Unwind> {
+        Ok(self.value.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct BinaryOperatorExpression {
-    pub l: Box<dyn ExpressionLiteral>,
-    pub r: Box<dyn ExpressionLiteral>,
-    pub op: BinaryOperator
-}
-
-impl ExpressionLiteral for BinaryOperatorExpression {
-    fn value(&self, mem_table: &HashMap<Identifier, Box<DataType>>) -> DataType {
-        return match self.op {
-            BinaryOperator::Add => {
-                let one = self.l.value(&mem_table);
-                let other = self.r.value(&mem_table);
-                one.add(other)
+    pub l: Box<Expr>,
+    pub r: Box<Expr>,
+    pub op: BinaryOperator,
+    pub span: Span
+}
+
+fn boolean_operand(value: DataType, context: &'static str) -> Result<DataType, RuntimeErrorKind> {
+    match value {
+        DataType::Boolean(_) => Ok(value),
+        other => Err(RuntimeErrorKind::NotABoolean { context, found: other.type_name() })
+    }
+}
+
+impl BinaryOperatorExpression {
+    pub fn value(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        let result = match self.op {
+            BinaryOperator::Or => {
+                // The right operand only runs when the left one is false.
+                match self.l.value(env)? {
+                    DataType::Boolean(true) => Ok(DataType::Boolean(true)),
+                    DataType::Boolean(false) => boolean_operand(self.r.value(env)?, "as an operand of `||`"),
+                    other => boolean_operand(other, "as an operand of `||`")
+                }
             }
-            BinaryOperator::Subtract => {
-                let one = self.l.value(&mem_table);
-                let other = self.r.value(&mem_table);
-                one.subtract(other)
+            BinaryOperator::And => {
+                // The right operand only runs when the left one is true.
+                match self.l.value(env)? {
+                    DataType::Boolean(false) => Ok(DataType::Boolean(false)),
+                    DataType::Boolean(true) => boolean_operand(self.r.value(env)?, "as an operand of `&&`"),
+                    other => boolean_operand(other, "as an operand of `&&`")
+                }
             }
-            BinaryOperator::Multiply => {
-                let one = self.l.value(&mem_table);
-                let other = self.r.value(&mem_table);
-                one.multiply(other)
+            op => {
+                let one = self.l.value(env)?;
+                let other = self.r.value(env)?;
+                one.binary_operation(op, other)
             }
-            BinaryOperator::Divide => {
-                let one = self.l.value(&mem_table);
-                let other = self.r.value(&mem_table);
-                one.divide(other)
+        };
+
+        result.map_err(|kind| RuntimeError::new(kind, self.span).into())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UnaryOperatorExpression {
+    pub inp: Box<Expr>,
+    pub op: UnaryOperator,
+    pub span: Span
+}
+
+impl UnaryOperatorExpression {
+    pub fn value(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        let operand = self.inp.value(env)?;
+
+        operand.unary_operation(self.op).map_err(|kind| RuntimeError::new(kind, self.span).into())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CallExpression {
+    pub callee: Box<Expr>,
+    pub arguments: Vec<Expr>,
+    pub span: Span
+}
+
+impl CallExpression {
+    pub fn value(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        let callee = self.callee.value(env)?;
+
+        let mut arguments = Vec::with_capacity(self.arguments.len());
+        for argument in &self.arguments {
+            arguments.push(argument.value(env)?);
+        }
+
+        match callee {
+            DataType::Builtin(builtin) => builtin.call(&arguments).map_err(|kind| RuntimeError::new(kind, self.span).into()),
+            DataType::Function(function) => Ok(function.call(arguments, self.span)?),
+            other => Err(RuntimeError::new(RuntimeErrorKind::NotCallable(other.type_name()), self.span).into())
+        }
+    }
+}
+
+/// A `{ ... }` body: its statements run in a scope of their own, and the
+/// trailing expression, if any, is the value of the block. A block without
+/// one evaluates to nil.
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub statements: Vec<Stmt>,
+    pub value: Option<Box<Expr>>,
+    pub span: Span
+}
+
+impl Block {
+    /// Runs the block in `env` as it is, without opening a scope for it.
+    pub(crate) fn evaluate(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        for statement in &self.statements {
+            statement.run(env)?;
+        }
+
+        match &self.value {
+            Some(value) => value.value(env),
+            None => Ok(DataType::Nil)
+        }
+    }
+
+    pub fn value(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        env.push_scope();
+        let result = self.evaluate(env);
+        env.pop_scope();
+        result
+    }
+}
+
+/// `if condition { ... } else ...`, evaluating to the value of whichever
+/// branch runs. `alternative` is either a `Block` or, for `else if`, another
+/// `IfExpression`. Without an `else`, a false condition evaluates to nil.
+#[derive(Debug, Clone)]
+pub struct IfExpression {
+    pub condition: Box<Expr>,
+    pub consequence: Block,
+    pub alternative: Option<Box<Expr>>,
+    pub span: Span
+}
+
+impl IfExpression {
+    pub fn value(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        match self.condition.value(env)? {
+            DataType::Boolean(true) => self.consequence.value(env),
+            DataType::Boolean(false) => match &self.alternative {
+                Some(alternative) => alternative.value(env),
+                None => Ok(DataType::Nil)
+            },
+            other => {
+                let kind = RuntimeErrorKind::NotABoolean { context: "as an `if` condition", found: other.type_name() };
+                Err(RuntimeError::new(kind, self.condition.span()).into())
             }
-            BinaryOperator::Or => {todo!()}
-            BinaryOperator::And => {todo!()}
         }
     }
 }
 
-#[derive(Debug)]
-pub struct UnaryOperatorExpression {
-    pub inp: Box<dyn ExpressionLiteral>,
-    pub op: UnaryOperator
+/// `fn(a, b) { ... }`. Evaluating it creates a function value that captures
+/// the current environment. The literal is shared with every function value
+/// made from it, which is why `Expr` holds it behind an `Rc`.
+#[derive(Debug, Clone)]
+pub struct FunctionLiteral {
+    pub name: Option<Rc<str>>,
+    pub parameters: Vec<Identifier>,
+    pub body: Block,
+    pub span: Span
+}
+
+impl FunctionLiteral {
+    pub fn value(literal: &Rc<FunctionLiteral>, env: &mut Environment) -> DataType {
+        DataType::Function(Rc::new(Function { declaration: Rc::clone(literal), env: env.clone() }))
+    }
+}
+
+/// `[a, b, c]`. Every evaluation creates a new list.
+#[derive(Debug, Clone)]
+pub struct ListLiteral {
+    pub items: Vec<Expr>,
+    pub span: Span
 }
 
-impl ExpressionLiteral for UnaryOperatorExpression {
-    fn value(&self, mem_table: &HashMap<Identifier, Box<DataType>>) -> DataType {
-        todo!()
+impl ListLiteral {
+    pub fn value(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        let mut items = Vec::with_capacity(self.items.len());
+        for item in &self.items {
+            items.push(item.value(env)?);
+        }
+        Ok(DataType::new_list(items))
+    }
+}
+
+/// `target[index]`.
+#[derive(Debug, Clone)]
+pub struct IndexExpression {
+    pub target: Box<Expr>,
+    pub index: Box<Expr>,
+    pub span: Span
+}
+
+impl IndexExpression {
+    pub fn value(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        let target = self.target.value(env)?;
+        let index = self.index.value(env)?;
+        target.index(&index).map_err(|kind| RuntimeError::new(kind, self.span).into())
     }
 }
 
-#[derive(Debug)]
-pub enum Expression {
-    Empty,
-    SingleValueExpression(Box<dyn ExpressionLiteral>),
-    BinaryOperatorExpression(BinaryOperatorExpression),
-    UnaryOperatorExpression(UnaryOperatorExpression)
+/// `target[start..end]`, where either bound may be left out.
+#[derive(Debug, Clone)]
+pub struct SliceExpression {
+    pub target: Box<Expr>,
+    pub start: Option<Box<Expr>>,
+    pub end: Option<Box<Expr>>,
+    pub span: Span
 }
 
-impl ExpressionLiteral for Expression {
-    fn value(&self, mem_table: &HashMap<Identifier, Box<DataType>>) -> DataType {
-        return match self {
-            Expression::Empty => {panic!("Empty expression called for execution")}
-            Expression::SingleValueExpression(v) => {v.value(&mem_table)}
-            Expression::BinaryOperatorExpression(v) => {v.value(&mem_table)}
-            Expression::UnaryOperatorExpression(v) => {v.value(&mem_table)}
+impl SliceExpression {
+    pub fn value(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        let target = self.target.value(env)?;
+        let start = match &self.start {
+            Some(start) => Some(start.value(env)?),
+            None => None
+        };
+        let end = match &self.end {
+            Some(end) => Some(end.value(env)?),
+            None => None
+        };
+        target.slice(start.as_ref(), end.as_ref()).map_err(|kind| RuntimeError::new(kind, self.span).into())
+    }
+}
+
+/// `{ "key": value, ... }`. Every evaluation creates a new map, with the keys
+/// in the order they are written. A key written twice keeps the last value.
+#[derive(Debug, Clone)]
+pub struct MapLiteral {
+    pub entries: Vec<(Rc<str>, Expr)>,
+    pub span: Span
+}
+
+impl MapLiteral {
+    pub fn value(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        let mut map = Map::new();
+        for (key, value) in &self.entries {
+            map.insert(Rc::clone(key), value.value(env)?);
         }
+        Ok(DataType::new_map(map))
     }
-}
\ No newline at end of file
+}
+
+/// `target.field`, which is shorthand for `target["field"]`.
+#[derive(Debug, Clone)]
+pub struct FieldExpression {
+    pub target: Box<Expr>,
+    pub field: Rc<str>,
+    pub span: Span
+}
+
+impl FieldExpression {
+    pub fn key(&self) -> DataType {
+        DataType::String(Rc::clone(&self.field))
+    }
+
+    pub fn value(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        let target = self.target.value(env)?;
+        target.index(&self.key()).map_err(|kind| RuntimeError::new(kind, self.span).into())
+    }
+}
@@ -0,0 +1,48 @@
+use std::rc::Rc;
+use crate::datatypes::datatypes::DataType;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::error::{RuntimeError, RuntimeErrorKind, Unwind};
+use crate::lexer::span::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identifier {
+    pub name: Rc<str>,
+    pub span: Span,
+    /// Set by the resolver for local variables: how many scopes out the
+    /// variable lives, and its slot there. Globals and builtins, and every
+    /// name in a program that was not resolved, are looked up by name.
+    pub resolved: Option<(usize, usize)>
+}
+
+impl Identifier {
+    pub fn new(name: Rc<str>, span: Span) -> Identifier {
+        Identifier { name, span, resolved: None }
+    }
+
+    pub fn value(&self, env: &mut Environment) -> Result<DataType, Unwind> {
+        let value = match self.resolved {
+            Some((depth, slot)) => env.get(depth, slot),
+            None => env.lookup(&self.name)
+        };
+        match value {
+            None => { Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(Rc::clone(&self.name)), self.span).into()) }
+            Some(data) => { Ok(data) }
+        }
+    }
+
+    /// Binds the name in the innermost scope, in its slot if it has one.
+    pub fn declare(&self, env: &mut Environment, value: DataType, mutable: bool) {
+        match (self.resolved, mutable) {
+            (Some((_, slot)), _) => env.declare(slot, value, mutable),
+            (None, true) => env.define_mutable(Rc::clone(&self.name), value),
+            (None, false) => env.define(Rc::clone(&self.name), value)
+        }
+    }
+
+    pub fn assign(&self, env: &mut Environment, value: DataType) -> Result<(), RuntimeErrorKind> {
+        match self.resolved {
+            Some((depth, slot)) => env.assign_slot(depth, slot, &self.name, value),
+            None => env.assign(&self.name, value)
+        }
+    }
+}
@@ -1,4 +1,7 @@
+#[allow(clippy::module_inception)]
 pub mod parser;
 pub mod statement;
 pub mod expression;
-pub mod identifier;
\ No newline at end of file
+pub mod identifier;
+pub mod error;
+pub mod visitor;
\ No newline at end of file
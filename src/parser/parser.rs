@@ -1,170 +1,787 @@
+use std::collections::VecDeque;
 use std::rc::Rc;
 use crate::datatypes::datatypes::DataType;
-use crate::lexer::token::Token;
-use crate::operators::operators::BinaryOperator;
-use crate::parser::expression::{BinaryOperatorExpression, Expression};
+use crate::lexer::span::Span;
+use crate::lexer::token::{SpannedToken, Token};
+use crate::operators::operators::{Associativity, BinaryOperator, Precedence, UnaryOperator};
+use crate::parser::error::{Expected, ParseError, ParseErrorKind};
+use crate::parser::expression::{BinaryOperatorExpression, Block, CallExpression, Expr, FunctionLiteral, FieldExpression, IfExpression, IndexExpression, ListLiteral, Literal, MapLiteral, SliceExpression, UnaryOperatorExpression};
 use crate::parser::identifier::Identifier;
-use crate::parser::statement::{EndStatement, LetStatement, Statement};
-
+use crate::parser::statement::{AssignStatement, AssignTarget, BreakStatement, ContinueStatement, ExpressionStatement, ForStatement, Iterable, LetStatement, ReturnStatement, Stmt, WhileStatement};
+
+/// Pulls tokens from any token stream on demand. The current token is held
+/// separately and a small buffer keeps the tokens that have been peeked at but
+/// not yet consumed, so the parser never needs the whole stream in memory.
+///
+/// Doc comments never reach the grammar: they are attached to the token that
+/// follows them and can be picked up with `take_doc`. One that nothing took
+/// by the time the parser moves past its token is kept to be reported.
 pub struct Parser {
-    tokens: Rc<[Token]>,
-    pos: usize,
-    peek: usize,
+    tokens: Box<dyn Iterator<Item = SpannedToken>>,
+    current: SpannedToken,
+    current_doc: Option<Doc>,
+    lookahead: VecDeque<(SpannedToken, Option<Doc>)>,
+    /// Doc comments followed by something other than a `let`.
+    dangling_docs: Vec<ParseError>,
+    /// How many expressions and blocks the current token is inside.
+    depth: usize,
+    /// How many `{` the current token is inside, going by the tokens alone,
+    /// so recovering from an error can find the end of the statement.
+    braces: usize,
+}
+
+/// The text of one or more `///` lines in a row, and where they are.
+struct Doc {
+    text: Rc<str>,
+    span: Span,
 }
 
+/// How deeply expressions and blocks may be nested. Anything deeper is a
+/// parse error, instead of overflowing the stack here or in whatever walks
+/// the tree afterwards.
+pub const MAX_NESTING: usize = 128;
+
 impl Parser {
-    pub fn new(tokens:Rc<[Token]>) -> Parser {
-        Parser {
+    pub fn new<I>(tokens: I) -> Parser where I: IntoIterator<Item = SpannedToken>, I::IntoIter: 'static {
+        let mut tokens: Box<dyn Iterator<Item = SpannedToken>> = Box::new(tokens.into_iter());
+        let (current, current_doc) = pull(tokens.as_mut(), Span::default());
+
+        let mut parser = Parser {
             tokens,
-            pos: 0,
-            peek: 1,
+            current,
+            current_doc,
+            lookahead: VecDeque::new(),
+            dangling_docs: Vec::new(),
+            depth: 0,
+            braces: 0,
+        };
+        parser.fill(1);
+        parser
+    }
+
+    /// Makes sure at least `n` tokens after the current one are buffered. A
+    /// stream that ends without an EOF token is treated as if it had one.
+    fn fill(&mut self, n: usize) {
+        while self.lookahead.len() < n {
+            let last = self.lookahead.back().map_or(&self.current, |(token, _)| token);
+
+            if last.token == Token::EOF {
+                let eof = last.clone();
+                self.lookahead.push_back((eof, None));
+                continue;
+            }
+
+            let eof = Span::new(last.span.end, last.span.end, last.span.line, last.span.column);
+            let next = pull(self.tokens.as_mut(), eof);
+            self.lookahead.push_back(next);
+        }
+    }
+
+    /// The doc comment written directly before the current token, if any,
+    /// which then counts as attached to what the token starts.
+    pub fn take_doc(&mut self) -> Option<Rc<str>> {
+        self.current_doc.take().map(|doc| doc.text)
+    }
+
+    /// Reports the doc comment before the current token, unless it was taken.
+    fn check_doc(&mut self) {
+        if let Some(doc) = self.current_doc.take() {
+            let error = ParseError::of(ParseErrorKind::DanglingDoc, &self.current.token, doc.span);
+            self.dangling_docs.push(error);
         }
     }
 
     pub fn current_token(&self) -> &Token {
-        &self.tokens[self.pos]
+        &self.current.token
     }
 
+    pub fn current_span(&self) -> Span {
+        self.current.span
+    }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn next(p: &mut Parser) -> &mut Parser {
-        if p.peek >= p.tokens.len() {
-            p.pos = p.tokens.len() - 1;
-            p.peek = 0;
-        } else {
-            p.pos = p.peek;
-            p.peek += 1;
+        match p.current.token {
+            Token::LBRACE => p.braces += 1,
+            Token::RBRACE => p.braces = p.braces.saturating_sub(1),
+            _ => {}
+        }
+        if p.current.token != Token::EOF {
+            p.check_doc();
+            (p.current, p.current_doc) = p.lookahead.pop_front().expect("lookahead is never empty");
+            p.fill(1);
         }
         p
     }
 
-    pub fn peek_token(&self) -> &Token {
-        if self.current_token() == &Token::EOF {
-            return self.current_token();
-        } else {
-            return &self.tokens[self.peek]
+    /// Goes one level deeper, failing if that is more than `MAX_NESTING`.
+    fn enter(&mut self) -> Result<(), ParseError> {
+        if self.depth >= MAX_NESTING {
+            return Err(ParseError::of(ParseErrorKind::TooDeep, self.current_token(), self.current_span()))
         }
+        self.depth += 1;
+        Ok(())
+    }
+
+    pub fn peek_span(&self) -> Span {
+        self.lookahead[0].0.span
+    }
+
+    pub fn peek_token(&self) -> &Token {
+        &self.lookahead[0].0.token
+    }
+
+    /// The token `n` places after the current one, reading further ahead in
+    /// the stream if needed. `peek_nth(1)` is the same as `peek_token`.
+    pub fn peek_nth(&mut self, n: usize) -> &Token {
+        self.fill(n);
+        &self.lookahead[n - 1].0.token
     }
 }
 
-#[derive(Debug)]
+/// Reads the next grammar token from `tokens`, joining any doc comments in
+/// front of it into one text. `eof` is the span to report if the stream ends
+/// without an EOF token of its own.
+fn pull(tokens: &mut dyn Iterator<Item = SpannedToken>, eof: Span) -> (SpannedToken, Option<Doc>) {
+    let mut doc: Option<(String, Span)> = None;
+
+    loop {
+        let token = match tokens.next() {
+            Some(SpannedToken { token: Token::DocComment(text), span }) => {
+                match doc.as_mut() {
+                    Some((doc, doc_span)) => {
+                        doc.push('\n');
+                        doc.push_str(&text);
+                        *doc_span = doc_span.to(span);
+                    }
+                    None => { doc = Some((String::from(&*text), span)) }
+                }
+                continue;
+            }
+            Some(token) => token,
+            None => SpannedToken { token: Token::EOF, span: eof }
+        };
+        return (token, doc.map(|(text, span)| Doc { text: Rc::from(text), span }))
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Program {
-    statements: Box<[Box<dyn Statement>]>
+    statements: Box<[Stmt]>
 }
 
 impl Program {
-    pub fn new_from_vec(statements: Vec<Box<dyn Statement>>) -> Program {
+    pub fn new_from_vec(statements: Vec<Stmt>) -> Program {
         Program { statements: statements.into_boxed_slice() }
     }
+
+    pub fn statements(&self) -> &[Stmt] {
+        &self.statements
+    }
+
+    pub fn statements_mut(&mut self) -> &mut [Stmt] {
+        &mut self.statements
+    }
 }
 
-fn parse_expression(p: &mut Parser) -> (Expression, &mut Parser) {
-    let mut p = p;
+fn binary_expression(op: BinaryOperator, l: Box<Expr>, r: Box<Expr>) -> Expr {
+    let span = l.span().to(r.span());
+    Expr::Binary(BinaryOperatorExpression{ op, l, r, span })
+}
+
+/// Parses an expression by precedence climbing. Operators binding no tighter
+/// than `min_precedence` are left for the caller, which is what makes
+/// `10 - 2 - 3` group as `(10 - 2) - 3`.
+///
+/// Leaves the parser on the first token after the expression.
+fn parse_expression(p: &mut Parser, min_precedence: Precedence) -> Result<(Expr, &mut Parser), ParseError> {
+    p.enter()?;
+    let (expression, p) = parse_operators(p, min_precedence)?;
+    p.depth -= 1;
+    Ok((expression, p))
+}
+
+fn parse_operators(p: &mut Parser, min_precedence: Precedence) -> Result<(Expr, &mut Parser), ParseError> {
+    let (mut left, mut p) = parse_prefix(p)?;
+
+    loop {
+        if p.current_token() == &Token::LPAREN {
+            let (call, next) = parse_call(Parser::next(p), left)?;
+            p = next;
+            left = call;
+            continue;
+        }
+
+        if p.current_token() == &Token::LBRACKET {
+            let (indexed, next) = parse_index(Parser::next(p), left)?;
+            p = next;
+            left = indexed;
+            continue;
+        }
+
+        if p.current_token() == &Token::DOT {
+            let (field, next) = parse_field(Parser::next(p), left)?;
+            p = next;
+            left = field;
+            continue;
+        }
+
+        let Some(op) = BinaryOperator::from_token(p.current_token()) else {
+            break;
+        };
+
+        let precedence = op.precedence();
+
+        if precedence <= min_precedence {
+            break;
+        }
+
+        let right_precedence = match op.associativity() {
+            Associativity::Left => precedence,
+            Associativity::Right => lower(precedence),
+        };
+
+        let (right, next) = parse_expression(Parser::next(p), right_precedence)?;
+        p = next;
+
+        left = binary_expression(op, Box::new(left), Box::new(right));
+    }
+
+    Ok((left, p))
+}
+
+/// The precedence level just below `precedence`, used so a right associative
+/// operator lets another of the same level bind its right operand.
+fn lower(precedence: Precedence) -> Precedence {
+    match precedence {
+        Precedence::Lowest | Precedence::Or => Precedence::Lowest,
+        Precedence::And => Precedence::Or,
+        Precedence::Equality => Precedence::And,
+        Precedence::Comparison => Precedence::Equality,
+        Precedence::Sum => Precedence::Comparison,
+        Precedence::Product => Precedence::Sum,
+        Precedence::Prefix => Precedence::Product,
+    }
+}
+
+/// Whether `token` can begin an expression, which has to agree with the cases
+/// `parse_prefix` handles.
+fn starts_expression(token: &Token) -> bool {
+    UnaryOperator::from_token(token).is_some() || matches!(token,
+        Token::IF | Token::FUNCTION | Token::LBRACE | Token::LBRACKET | Token::LPAREN | Token::IDENTIFIER(_)
+        | Token::Number(_) | Token::String(_) | Token::TRUE | Token::FALSE)
+}
+
+/// Parses an operand: a literal, an identifier, a parenthesised expression, a
+/// block, a list, a map, or an operand preceded by a prefix operator.
+fn parse_prefix(p: &mut Parser) -> Result<(Expr, &mut Parser), ParseError> {
+    if p.current_token() == &Token::LBRACE && starts_map(p) {
+        return parse_map(p)
+    }
+
     let curr = p.current_token();
-    let mut expression = Expression::Empty;
+    let span = p.current_span();
+
+    if let Some(op) = UnaryOperator::from_token(curr) {
+        let (operand, p) = parse_expression(Parser::next(p), Precedence::Prefix)?;
+        let span = span.to(operand.span());
+        let expression = UnaryOperatorExpression { inp: Box::new(operand), op, span };
+        return Ok((Expr::Unary(expression), p))
+    }
 
     match curr {
-            Token::Number(_) => {
-                let nex = p.peek_token();
-                match nex {
-                    &Token::SEMICOLON | &Token::RPAREN => {
-                        expression = Expression::SingleValueExpression(Box::new(DataType::from_token(&curr)));
-                        p = Parser::next(p);
-                    },
-                    &Token::ADD | &Token::SUBTRACT | &Token::DIVISION | &Token::MULTIPLICATION => {
-                        let op = BinaryOperator::from_token(nex);
-
-                        let left = curr.clone();
-
-                        p = Parser::next(p);
-                        p = Parser::next(p);
-
-                        let right = parse_expression(p);
-
-                        expression = Expression::BinaryOperatorExpression(
-                            BinaryOperatorExpression{
-                                op,
-                                l: Box::new(DataType::from_token(&left)),
-                                r: Box::new(right.0)
-                            });
-
-                        p = right.1;
-                    }
-                    _ => {panic!("unhandled expression type")}
-                }
-            },
-            Token::IDENTIFIER(v) => {
-                let nex = p.peek_token();
-                match nex {
-                    &Token::SEMICOLON | &Token::RPAREN => {
-                        expression = Expression::SingleValueExpression(Box::new(Identifier(Rc::clone(v))));
-                        p = Parser::next(p);
-                    },
-                    &Token::ADD | &Token::SUBTRACT | &Token::DIVISION | &Token::MULTIPLICATION => {
-                        let op = BinaryOperator::from_token(nex);
-
-                        let left = v.clone();
-
-                        p = Parser::next(p);
-                        p = Parser::next(p);
-
-                        let right = parse_expression(p);
-
-                        expression = Expression::BinaryOperatorExpression(
-                            BinaryOperatorExpression{
-                                op,
-                                l: Box::new(Identifier(Rc::clone(&left))),
-                                r: Box::new(right.0)
-                            });
-
-                        p = right.1;
-                    }
-                    _ => {panic!("unhandled expression type")}
+        Token::IF => parse_if(p),
+        Token::LBRACE => {
+            let (block, p) = parse_block(p)?;
+            Ok((Expr::Block(block), p))
+        }
+        Token::LBRACKET => parse_list(p),
+        Token::FUNCTION => parse_function(p),
+        Token::Number(_) | Token::String(_) | Token::TRUE | Token::FALSE => {
+            let literal = Literal { value: DataType::from_token(curr, span)?, span };
+            Ok((Expr::Literal(literal), Parser::next(p)))
+        }
+        Token::IDENTIFIER(v) => {
+            let identifier = Identifier::new(Rc::clone(v), span);
+            Ok((Expr::Identifier(identifier), Parser::next(p)))
+        }
+        Token::LPAREN => {
+            let (expression, p) = parse_expression(Parser::next(p), Precedence::Lowest)?;
+
+            if p.current_token() != &Token::RPAREN {
+                return Err(ParseError::new(vec![Expected::Token(Token::RPAREN)], p.current_token(), p.current_span()))
+            }
+
+            Ok((expression, Parser::next(p)))
+        }
+        _ => {Err(ParseError::new(vec![Expected::Expression], curr, span))}
+    }
+}
+
+/// Parses the argument list of a call, starting just after the `(`. A call
+/// binds tighter than any operator, so `f(x) * 2` multiplies the result.
+fn parse_call(p: &mut Parser, callee: Expr) -> Result<(Expr, &mut Parser), ParseError> {
+    let mut p = p;
+    let mut arguments = vec![];
+
+    if p.current_token() != &Token::RPAREN {
+        loop {
+            let (argument, next) = parse_expression(p, Precedence::Lowest)?;
+            p = next;
+            arguments.push(argument);
+
+            match p.current_token() {
+                Token::COMMA => { p = Parser::next(p) }
+                Token::RPAREN => { break }
+                other => {
+                    return Err(ParseError::new(vec![Expected::Token(Token::COMMA), Expected::Token(Token::RPAREN)], other, p.current_span()))
                 }
-            },
-            &Token::LPAREN => {
+            }
+        }
+    }
+
+    let span = callee.span().to(p.current_span());
+    let call = CallExpression { callee: Box::new(callee), arguments, span };
+    Ok((Expr::Call(call), Parser::next(p)))
+}
+
+/// Parses `[a, b, c]` starting on the `[`. A trailing comma is allowed.
+fn parse_list(p: &mut Parser) -> Result<(Expr, &mut Parser), ParseError> {
+    let start = p.current_span();
+    let mut p = Parser::next(p);
+    let mut items = vec![];
+
+    while p.current_token() != &Token::RBRACKET {
+        let (item, next) = parse_expression(p, Precedence::Lowest)?;
+        p = next;
+        items.push(item);
+
+        match p.current_token() {
+            Token::COMMA => { p = Parser::next(p) }
+            Token::RBRACKET => {}
+            other => {
+                return Err(ParseError::new(vec![Expected::Token(Token::COMMA), Expected::Token(Token::RBRACKET)], other, p.current_span()))
+            }
+        }
+    }
+
+    let span = start.to(p.current_span());
+    Ok((Expr::List(ListLiteral { items, span }), Parser::next(p)))
+}
+
+/// Whether the `{` the parser is on opens a map rather than a block: either
+/// `{}` or a `{` followed by a string and a `:`. A block can't start that way,
+/// since a string is never followed by `:` in an expression.
+fn starts_map(p: &mut Parser) -> bool {
+    match p.peek_nth(1) {
+        Token::RBRACE => true,
+        Token::String(_) => p.peek_nth(2) == &Token::COLON,
+        _ => false
+    }
+}
+
+/// Parses `{ "key": value, ... }` starting on the `{`. A trailing comma is
+/// allowed.
+fn parse_map(p: &mut Parser) -> Result<(Expr, &mut Parser), ParseError> {
+    let start = p.current_span();
+    let mut p = Parser::next(p);
+    let mut entries = vec![];
+
+    while p.current_token() != &Token::RBRACE {
+        let key = match p.current_token() {
+            Token::String(key) => Rc::clone(key),
+            other => {
+                return Err(ParseError::new(vec![Expected::MapKey, Expected::Token(Token::RBRACE)], other, p.current_span()))
+            }
+        };
+
+        p = Parser::next(p);
+        if p.current_token() != &Token::COLON {
+            return Err(ParseError::new(vec![Expected::Token(Token::COLON)], p.current_token(), p.current_span()))
+        }
+
+        let (value, next) = parse_expression(Parser::next(p), Precedence::Lowest)?;
+        p = next;
+        entries.push((key, value));
+
+        match p.current_token() {
+            Token::COMMA => { p = Parser::next(p) }
+            Token::RBRACE => {}
+            other => {
+                return Err(ParseError::new(vec![Expected::Token(Token::COMMA), Expected::Token(Token::RBRACE)], other, p.current_span()))
+            }
+        }
+    }
+
+    let span = start.to(p.current_span());
+    Ok((Expr::Map(MapLiteral { entries, span }), Parser::next(p)))
+}
+
+/// Parses the name after the `.` of `target.field`.
+fn parse_field(p: &mut Parser, target: Expr) -> Result<(Expr, &mut Parser), ParseError> {
+    match p.current_token() {
+        Token::IDENTIFIER(field) => {
+            let span = target.span().to(p.current_span());
+            let field = FieldExpression { target: Box::new(target), field: Rc::clone(field), span };
+            Ok((Expr::Field(field), Parser::next(p)))
+        }
+        other => Err(ParseError::new(vec![Expected::Identifier], other, p.current_span()))
+    }
+}
+
+/// Parses what follows the `[` of `target[index]` or `target[start..end]`.
+/// Either bound of a slice may be left out, as in `xs[1..]`.
+fn parse_index(p: &mut Parser, target: Expr) -> Result<(Expr, &mut Parser), ParseError> {
+    let mut p = p;
+    let mut start = None;
+
+    if p.current_token() != &Token::DOTDOT {
+        let (index, next) = parse_expression(p, Precedence::Lowest)?;
+        p = next;
+
+        if p.current_token() == &Token::RBRACKET {
+            let span = target.span().to(p.current_span());
+            let index = IndexExpression { target: Box::new(target), index: Box::new(index), span };
+            return Ok((Expr::Index(index), Parser::next(p)))
+        }
+
+        start = Some(Box::new(index));
+    }
+
+    if p.current_token() != &Token::DOTDOT {
+        return Err(ParseError::new(vec![Expected::Token(Token::DOTDOT), Expected::Token(Token::RBRACKET)], p.current_token(), p.current_span()))
+    }
+
+    p = Parser::next(p);
+    let mut end = None;
+
+    if p.current_token() != &Token::RBRACKET {
+        let (bound, next) = parse_expression(p, Precedence::Lowest)?;
+        p = next;
+        end = Some(Box::new(bound));
+    }
+
+    if p.current_token() != &Token::RBRACKET {
+        return Err(ParseError::new(vec![Expected::Token(Token::RBRACKET)], p.current_token(), p.current_span()))
+    }
+
+    let span = target.span().to(p.current_span());
+    let slice = SliceExpression { target: Box::new(target), start, end, span };
+    Ok((Expr::Slice(slice), Parser::next(p)))
+}
+
+/// Parses `if condition { ... }` with any `else if` / `else` branches,
+/// starting on the `if`.
+fn parse_if(p: &mut Parser) -> Result<(Expr, &mut Parser), ParseError> {
+    let start = p.current_span();
+    let (condition, p) = parse_expression(Parser::next(p), Precedence::Lowest)?;
+    let (consequence, mut p) = parse_block(p)?;
+    let mut alternative: Option<Box<Expr>> = None;
+
+    if p.current_token() == &Token::ELSE {
+        p = Parser::next(p);
+
+        match p.current_token() {
+            Token::IF => {
+                let (nested, next) = parse_if(p)?;
+                p = next;
+                alternative = Some(Box::new(nested));
+            }
+            Token::LBRACE => {
+                let (block, next) = parse_block(p)?;
+                p = next;
+                alternative = Some(Box::new(Expr::Block(block)));
+            }
+            other => {
+                return Err(ParseError::new(vec![Expected::Token(Token::IF), Expected::Token(Token::LBRACE)], other, p.current_span()))
+            }
+        }
+    }
+
+    let end = alternative.as_ref().map_or(consequence.span, |a| a.span());
+    let expression = IfExpression { condition: Box::new(condition), consequence, alternative, span: start.to(end) };
+    Ok((Expr::If(expression), p))
+}
+
+/// Parses `{ statements... value }` starting on the `{`, leaving the parser on
+/// the token after the closing `}`. An expression directly before the `}`
+/// with no `;` after it is the value of the block.
+fn parse_block(p: &mut Parser) -> Result<(Block, &mut Parser), ParseError> {
+    if p.current_token() != &Token::LBRACE {
+        return Err(ParseError::new(vec![Expected::Token(Token::LBRACE)], p.current_token(), p.current_span()))
+    }
+
+    p.enter()?;
+    let start = p.current_span();
+    let mut p = Parser::next(p);
+    let mut statements: Vec<Stmt> = vec![];
+    let mut value = None;
+
+    while p.current_token() != &Token::RBRACE {
+        if p.current_token() == &Token::SEMICOLON {
+            p = Parser::next(p);
+            continue;
+        }
+
+        if starts_statement(p) {
+            let (statement, next) = parse_statement(p)?;
+            p = next;
+            statements.push(statement);
+            continue;
+        }
+
+        if !starts_expression(p.current_token()) {
+            let expected = vec![Expected::Statement, Expected::Token(Token::RBRACE)];
+            return Err(ParseError::new(expected, p.current_token(), p.current_span()))
+        }
+
+        let (expression, next) = parse_expression(p, Precedence::Lowest)?;
+
+        if next.current_token() == &Token::RBRACE {
+            p = next;
+            value = Some(Box::new(expression));
+            break;
+        }
+
+        let (statement, next) = end_expression_statement(next, expression, true)?;
+        p = next;
+        statements.push(statement);
+    }
+
+    let block = Block { statements, value, span: start.to(p.current_span()) };
+    p.depth -= 1;
+    Ok((block, Parser::next(p)))
+}
+
+/// Parses `fn(a, b) { ... }` starting on the `fn`.
+fn parse_function(p: &mut Parser) -> Result<(Expr, &mut Parser), ParseError> {
+    let start = p.current_span();
+    let mut p = Parser::next(p);
+
+    if p.current_token() != &Token::LPAREN {
+        return Err(ParseError::new(vec![Expected::Token(Token::LPAREN)], p.current_token(), p.current_span()))
+    }
+
+    p = Parser::next(p);
+    let mut parameters = vec![];
+
+    while p.current_token() != &Token::RPAREN {
+        match p.current_token() {
+            Token::IDENTIFIER(name) => {
+                parameters.push(Identifier::new(Rc::clone(name), p.current_span()));
                 p = Parser::next(p);
-                let intrnl_expr_res  = parse_expression(p);
-                p = intrnl_expr_res.1;
+            }
+            other => {
+                return Err(ParseError::new(vec![Expected::Identifier, Expected::Token(Token::RPAREN)], other, p.current_span()))
+            }
+        }
 
-                let nex = p.peek_token();
+        match p.current_token() {
+            Token::COMMA => { p = Parser::next(p) }
+            Token::RPAREN => {}
+            other => {
+                return Err(ParseError::new(vec![Expected::Token(Token::COMMA), Expected::Token(Token::RPAREN)], other, p.current_span()))
+            }
+        }
+    }
 
-                match nex {
-                    &Token::SEMICOLON => {
-                        expression = intrnl_expr_res.0;
-                        p = Parser::next(p);
-                        p = Parser::next(p);
-                    }
-                    &Token::ADD | &Token::SUBTRACT | &Token::DIVISION | &Token::MULTIPLICATION => {
-                        let op = BinaryOperator::from_token(nex);
-                        let left = intrnl_expr_res.0;
+    let (body, p) = parse_block(Parser::next(p))?;
+    let span = start.to(body.span);
+    let function = FunctionLiteral { name: None, parameters, body, span };
+    Ok((Expr::Function(Rc::new(function)), p))
+}
 
-                        p = Parser::next(p);
-                        p = Parser::next(p);
+/// Whether the current token starts a statement other than an expression
+/// statement or assignment. `name:` starts a labeled loop.
+fn starts_statement(p: &Parser) -> bool {
+    match p.current_token() {
+        Token::LET | Token::RETURN | Token::WHILE | Token::FOR | Token::BREAK | Token::CONTINUE => true,
+        Token::IDENTIFIER(_) => p.peek_token() == &Token::COLON,
+        _ => false
+    }
+}
 
-                        let right = parse_expression(p);
+/// Parses a statement starting on its first token, leaving the parser on the
+/// token after it.
+fn parse_statement(p: &mut Parser) -> Result<(Stmt, &mut Parser), ParseError> {
+    let start = p.current_span();
 
-                        expression = Expression::BinaryOperatorExpression(
-                            BinaryOperatorExpression{
-                                op,
-                                l: Box::new(left),
-                                r: Box::new(right.0)
-                            });
+    match p.current_token() {
+        Token::LET => {
+            let doc = p.take_doc();
+            let (statement, p) = parse_let(Parser::next(p), start, doc)?;
+            Ok((Stmt::Let(statement), Parser::next(p)))
+        }
+        Token::RETURN => {
+            let (statement, p) = parse_return(Parser::next(p), start)?;
+            Ok((Stmt::Return(statement), Parser::next(p)))
+        }
+        Token::BREAK | Token::CONTINUE => parse_loop_control(p),
+        Token::WHILE | Token::FOR => parse_loop(p, start, None),
+        Token::IDENTIFIER(label) if p.peek_token() == &Token::COLON => {
+            let label = Rc::clone(label);
+            parse_loop(Parser::next(Parser::next(p)), start, Some(label))
+        }
+        token if starts_expression(token) => {
+            let (expression, p) = parse_expression(p, Precedence::Lowest)?;
+            end_expression_statement(p, expression, false)
+        }
+        other => Err(ParseError::new(vec![Expected::Statement], other, start))
+    }
+}
 
-                        p = right.1;
-                    },
-                    _ => {panic!("Invalid Token")}
-                }
+/// Makes a statement out of an expression the parser has just read, consuming
+/// the `;` after it. Expressions ending in a block, like `if`, don't need one.
+/// If an assignment operator follows instead, the expression is the target of
+/// an assignment. `in_block` only changes what the error says was expected.
+fn end_expression_statement(p: &mut Parser, expression: Expr, in_block: bool) -> Result<(Stmt, &mut Parser), ParseError> {
+    let assigns = p.current_token() == &Token::ASSIGN || BinaryOperator::from_assignment_token(p.current_token()).is_some();
+
+    match expression {
+        Expr::Identifier(variable) if assigns => {
+            let (statement, p) = parse_assign(p, AssignTarget::Variable(variable))?;
+            return Ok((Stmt::Assign(statement), Parser::next(p)))
+        }
+        Expr::Index(index) if assigns => {
+            let (statement, p) = parse_assign(p, AssignTarget::Index(index))?;
+            return Ok((Stmt::Assign(statement), Parser::next(p)))
+        }
+        Expr::Field(field) if assigns => {
+            let (statement, p) = parse_assign(p, AssignTarget::Field(field))?;
+            return Ok((Stmt::Assign(statement), Parser::next(p)))
+        }
+        _ => {}
+    }
+
+    if p.current_token() == &Token::SEMICOLON {
+        let span = expression.span().to(p.current_span());
+        return Ok((Stmt::Expression(ExpressionStatement { expression, span }), Parser::next(p)))
+    }
+
+    if expression.ends_with_block() {
+        let span = expression.span();
+        return Ok((Stmt::Expression(ExpressionStatement { expression, span }), p))
+    }
+
+    let mut expected = vec![Expected::Token(Token::SEMICOLON)];
+    if in_block {
+        expected.push(Expected::Token(Token::RBRACE));
+    }
+    Err(ParseError::new(expected, p.current_token(), p.current_span()))
+}
+
+/// Parses a `while` or `for` loop starting on its keyword. `start` is where
+/// the statement began, which is the label if the loop has one.
+fn parse_loop(p: &mut Parser, start: Span, label: Option<Rc<str>>) -> Result<(Stmt, &mut Parser), ParseError> {
+    match p.current_token() {
+        Token::WHILE => {
+            let (condition, p) = parse_expression(Parser::next(p), Precedence::Lowest)?;
+            let (body, p) = parse_block(p)?;
+            let span = start.to(body.span);
+            Ok((Stmt::While(WhileStatement { label, condition, body, span }), p))
+        }
+        Token::FOR => {
+            let p = Parser::next(p);
+
+            let variable = match p.current_token() {
+                Token::IDENTIFIER(name) => Identifier::new(Rc::clone(name), p.current_span()),
+                other => return Err(ParseError::new(vec![Expected::Identifier], other, p.current_span()))
+            };
+
+            let p = Parser::next(p);
+            if p.current_token() != &Token::IN {
+                return Err(ParseError::new(vec![Expected::Token(Token::IN)], p.current_token(), p.current_span()))
             }
-            _ => {panic!("Invalid token")}
+
+            let (value, p) = parse_expression(Parser::next(p), Precedence::Lowest)?;
+            let (iterable, p) = if p.current_token() == &Token::DOTDOT {
+                let (end, p) = parse_expression(Parser::next(p), Precedence::Lowest)?;
+                (Iterable::Range { start: value, end }, p)
+            } else {
+                (Iterable::Value(value), p)
+            };
+
+            let (body, p) = parse_block(p)?;
+            let span = start.to(body.span);
+            Ok((Stmt::For(ForStatement { label, variable, iterable, body, span }), p))
         }
-    (expression, p)
+        other => {
+            Err(ParseError::new(vec![Expected::Token(Token::WHILE), Expected::Token(Token::FOR)], other, p.current_span()))
+        }
+    }
+}
+
+/// Parses `break;` or `continue;`, either of which may name the loop it
+/// applies to, as in `break outer;`.
+fn parse_loop_control(p: &mut Parser) -> Result<(Stmt, &mut Parser), ParseError> {
+    let start = p.current_span();
+    let is_break = p.current_token() == &Token::BREAK;
+    let mut p = Parser::next(p);
+    let mut label = None;
+
+    if let Token::IDENTIFIER(name) = p.current_token() {
+        label = Some(Rc::clone(name));
+        p = Parser::next(p);
+    }
+
+    if p.current_token() != &Token::SEMICOLON {
+        return Err(ParseError::new(vec![Expected::Token(Token::SEMICOLON)], p.current_token(), p.current_span()))
+    }
+
+    let span = start.to(p.current_span());
+    let statement = if is_break {
+        Stmt::Break(BreakStatement { label, span })
+    } else {
+        Stmt::Continue(ContinueStatement { label, span })
+    };
+    Ok((statement, Parser::next(p)))
+}
+
+fn parse_return(p: &mut Parser, start: Span) -> Result<(ReturnStatement, &mut Parser), ParseError> {
+    if p.current_token() == &Token::SEMICOLON {
+        let span = start.to(p.current_span());
+        return Ok((ReturnStatement { value: None, span }, p))
+    }
+
+    let (value, p) = parse_expression(p, Precedence::Lowest)?;
+
+    if p.current_token() != &Token::SEMICOLON {
+        return Err(ParseError::new(vec![Expected::Token(Token::SEMICOLON)], p.current_token(), p.current_span()))
+    }
+
+    let span = start.to(p.current_span());
+    Ok((ReturnStatement { value: Some(value), span }, p))
+}
+
+/// Parses the rest of an assignment starting on its `=` or compound operator,
+/// leaving the parser on the closing `;`.
+fn parse_assign(p: &mut Parser, target: AssignTarget) -> Result<(AssignStatement, &mut Parser), ParseError> {
+    let start = match &target {
+        AssignTarget::Variable(variable) => variable.span,
+        AssignTarget::Index(index) => index.span,
+        AssignTarget::Field(field) => field.span,
+    };
+    let op = BinaryOperator::from_assignment_token(p.current_token());
+    let (value, p) = parse_expression(Parser::next(p), Precedence::Lowest)?;
+
+    if p.current_token() != &Token::SEMICOLON {
+        return Err(ParseError::new(vec![Expected::Token(Token::SEMICOLON)], p.current_token(), p.current_span()))
+    }
+
+    let span = start.to(p.current_span());
+    Ok((AssignStatement { target, op, value, span }, p))
 }
 
-fn parse_let(p: &mut Parser) -> (LetStatement ,&mut Parser) {
+fn parse_let(p: &mut Parser, start: Span, doc: Option<Rc<str>>) -> Result<(LetStatement, &mut Parser), ParseError> {
     let mut p = p;
+    let mutable = p.current_token() == &Token::MUT;
 
-    let mut new_identifer = Identifier(Rc::from(""));
+    if mutable {
+        p = Parser::next(p);
+    }
 
     let curr = p.current_token();
+    let span = p.current_span();
 
     match curr {
        Token::IDENTIFIER(val) => {
@@ -173,45 +790,103 @@ fn parse_let(p: &mut Parser) -> (LetStatement ,&mut Parser) {
 
            match nex {
                Token::ASSIGN => {
-                   new_identifer = Identifier(Rc::clone(val));
+                   let new_identifer = Identifier::new(Rc::clone(val), span);
 
                    p = Parser::next(p);
                    p = Parser::next(p);
 
-                   let expr_parse_res = parse_expression(p);
+                   let mut expr_parse_res = parse_expression(p, Precedence::Lowest)?;
+                   p = expr_parse_res.1;
+
+                   // `let f = fn(...) { ... };` gives the function its name for error messages.
+                   if let Expr::Function(function) = &mut expr_parse_res.0 {
+                       Rc::make_mut(function).name.get_or_insert_with(|| Rc::clone(&new_identifer.name));
+                   }
 
-                   let statement = LetStatement{ identifier: new_identifer, value: expr_parse_res.0 };
-                   return (statement, expr_parse_res.1)
+                   if p.current_token() != &Token::SEMICOLON {
+                       return Err(ParseError::new(vec![Expected::Token(Token::SEMICOLON)], p.current_token(), p.current_span()))
+                   }
+
+                   let span = start.to(p.current_span());
+                   let statement = LetStatement{ identifier: new_identifer, value: expr_parse_res.0, mutable, span, doc };
+                   Ok((statement, p))
                },
-               _ => {panic!("Expected '='")}
+               _ => {Err(ParseError::new(vec![Expected::Token(Token::ASSIGN)], nex, p.peek_span()))}
            }
 
        }
-        _ => {panic!("Identifier expected")}
+        _ => {Err(ParseError::new(vec![Expected::Identifier], curr, span))}
     }
 }
 
-pub fn parse(p: &mut Parser) -> Program {
+/// Skips ahead to the end of the broken statement so parsing can resume at the
+/// next one. The rest of any block the error was in is skipped with it, so
+/// the parser stops on the `;` or `}` that closes the statement, on a keyword
+/// that starts the next one, or on EOF.
+fn synchronize(p: &mut Parser, braces: usize) -> &mut Parser {
     let mut p = p;
-    let mut statements: Vec<Box<dyn Statement>> = vec![];
 
-    let mut curr = p.current_token();
+    while p.braces > braces || !matches!(p.current_token(),
+        Token::SEMICOLON | Token::RBRACE | Token::EOF
+        | Token::LET | Token::RETURN | Token::WHILE | Token::FOR | Token::BREAK | Token::CONTINUE) {
+        if p.current_token() == &Token::EOF {
+            break;
+        }
+        p = Parser::next(p);
+    }
+
+    p
+}
 
-    while curr != &Token::EOF {
+/// Parses the whole token stream. Syntax errors don't stop the parse: after
+/// each one the parser resynchronises at the next statement boundary, so every
+/// error in the script is reported at once.
+pub fn parse(p: &mut Parser) -> Result<Program, Vec<ParseError>> {
+    let mut p = p;
+    let mut statements: Vec<Stmt> = vec![];
+    let mut errors: Vec<ParseError> = vec![];
 
-        match curr {
-            &Token::LET{..} => {
-                let parse_res = parse_let(Parser::next(p));
-                p = parse_res.1;
-                statements.push(Box::new(parse_res.0))
-            },
-            _ => {}
+    while p.current_token() != &Token::EOF {
+        if p.current_token() == &Token::SEMICOLON {
+            p = Parser::next(p);
+            continue;
+        }
+
+        // An error leaves the expressions and blocks it was in without
+        // leaving them, so every statement starts the count afresh.
+        p.depth = 0;
+        let braces = p.braces;
+        match parse_statement(p) {
+            Ok((statement, _)) => {
+                statements.push(statement);
+            }
+            // There is no telling where a statement nested that deeply ends.
+            Err(err) if err.kind == ParseErrorKind::TooDeep => {
+                errors.push(err);
+                break;
+            }
+            Err(err) => {
+                errors.push(err);
+                p = synchronize(p, braces);
+
+                // Neither the `;` ending the statement nor a `}` closing
+                // nothing is a statement of its own.
+                while matches!(p.current_token(), Token::SEMICOLON | Token::RBRACE) {
+                    p = Parser::next(p);
+                }
+            }
         }
+    }
 
-        curr = Parser::next(p).current_token()
+    p.check_doc();
+    if !p.dangling_docs.is_empty() {
+        errors.append(&mut p.dangling_docs);
+        errors.sort_by_key(|error| error.span.start);
     }
 
-    statements.push(Box::new(EndStatement));
-    Program::new_from_vec(statements)
-}
+    if !errors.is_empty() {
+        return Err(errors)
+    }
 
+    Ok(Program::new_from_vec(statements))
+}
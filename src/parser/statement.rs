@@ -1,26 +1,323 @@
-use std::fmt::Debug;
-use crate::parser::expression::Expression;
+use std::rc::Rc;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::error::{RuntimeError, RuntimeErrorKind, Unwind};
+use crate::lexer::span::Span;
+use crate::datatypes::datatypes::{DataType, NumberType};
+use crate::parser::expression::{Block, Expr, FieldExpression, IndexExpression};
+use crate::operators::operators::BinaryOperator;
 use crate::parser::identifier::Identifier;
 
-pub trait Statement: Debug {
-    fn run(&self);
+/// A node of the statement tree. Statements are run for their effect on the
+/// environment and, unlike expressions, have no value.
+#[derive(Debug, Clone)]
+pub enum Stmt {
+    Let(LetStatement),
+    Assign(AssignStatement),
+    Expression(ExpressionStatement),
+    Return(ReturnStatement),
+    Break(BreakStatement),
+    Continue(ContinueStatement),
+    While(WhileStatement),
+    For(ForStatement)
 }
 
-#[derive(Debug)]
-pub struct EndStatement;
+impl Stmt {
+    pub fn run(&self, env: &mut Environment) -> Result<(), Unwind> {
+        match self {
+            Stmt::Let(v) => v.run(env),
+            Stmt::Assign(v) => v.run(env),
+            Stmt::Expression(v) => v.run(env),
+            Stmt::Return(v) => v.run(env),
+            Stmt::Break(v) => v.run(env),
+            Stmt::Continue(v) => v.run(env),
+            Stmt::While(v) => v.run(env),
+            Stmt::For(v) => v.run(env),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Let(v) => v.span,
+            Stmt::Assign(v) => v.span,
+            Stmt::Expression(v) => v.span,
+            Stmt::Return(v) => v.span,
+            Stmt::Break(v) => v.span,
+            Stmt::Continue(v) => v.span,
+            Stmt::While(v) => v.span,
+            Stmt::For(v) => v.span,
+        }
+    }
 
-impl Statement for EndStatement{
-    fn run(&self) {}
+    /// The `///` documentation written above the statement.
+    pub fn doc(&self) -> Option<Rc<str>> {
+        match self {
+            Stmt::Let(v) => v.doc.clone(),
+            _ => None
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LetStatement {
     pub(crate) identifier: Identifier,
-    pub(crate) value: Expression
+    pub(crate) value: Expr,
+    pub(crate) mutable: bool,
+    pub(crate) span: Span,
+    pub(crate) doc: Option<Rc<str>>
+}
+
+impl LetStatement {
+    pub fn run(&self, env: &mut Environment) -> Result<(), Unwind> {
+        let value = self.value.value(env)?;
+        self.identifier.declare(env, value, self.mutable);
+        Ok(())
+    }
+}
+
+/// An expression evaluated for its side effects, such as a call, with its
+/// value thrown away.
+#[derive(Debug, Clone)]
+pub struct ExpressionStatement {
+    pub(crate) expression: Expr,
+    pub(crate) span: Span
+}
+
+impl ExpressionStatement {
+    pub fn run(&self, env: &mut Environment) -> Result<(), Unwind> {
+        self.expression.value(env)?;
+        Ok(())
+    }
+}
+
+/// What an assignment writes to: a `let mut` variable, an item of a list or
+/// an entry of a map.
+#[derive(Debug, Clone)]
+pub enum AssignTarget {
+    Variable(Identifier),
+    Index(IndexExpression),
+    Field(FieldExpression)
+}
+
+/// `target = value;`, or a compound assignment such as `target += value;`
+/// when `op` is set.
+#[derive(Debug, Clone)]
+pub struct AssignStatement {
+    pub(crate) target: AssignTarget,
+    pub(crate) op: Option<BinaryOperator>,
+    pub(crate) value: Expr,
+    pub(crate) span: Span
+}
+
+impl AssignStatement {
+    pub fn run(&self, env: &mut Environment) -> Result<(), Unwind> {
+        match &self.target {
+            AssignTarget::Variable(variable) => {
+                let mut value = self.value.value(env)?;
+
+                if let Some(op) = self.op {
+                    let current = variable.value(env)?;
+                    value = current.binary_operation(op, value).map_err(|kind| RuntimeError::new(kind, self.span))?;
+                }
+
+                variable.assign(env, value).map_err(|kind| RuntimeError::new(kind, variable.span))?;
+            }
+            AssignTarget::Index(target) => {
+                let container = target.target.value(env)?;
+                let index = target.index.value(env)?;
+                self.assign_item(env, container, index, target.span)?;
+            }
+            AssignTarget::Field(target) => {
+                let container = target.target.value(env)?;
+                self.assign_item(env, container, target.key(), target.span)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn assign_item(&self, env: &mut Environment, container: DataType, index: DataType, span: Span) -> Result<(), Unwind> {
+        let mut value = self.value.value(env)?;
+
+        if let Some(op) = self.op {
+            let current = container.index(&index).map_err(|kind| RuntimeError::new(kind, span))?;
+            value = current.binary_operation(op, value).map_err(|kind| RuntimeError::new(kind, self.span))?;
+        }
+
+        container.set_index(&index, value).map_err(|kind| RuntimeError::new(kind, span))?;
+        Ok(())
+    }
+}
+
+/// `return value;` or a bare `return;`, which returns nil.
+#[derive(Debug, Clone)]
+pub struct ReturnStatement {
+    pub(crate) value: Option<Expr>,
+    pub(crate) span: Span
+}
+
+impl ReturnStatement {
+    pub fn run(&self, env: &mut Environment) -> Result<(), Unwind> {
+        let value = match &self.value {
+            Some(value) => value.value(env)?,
+            None => DataType::Nil
+        };
+        Err(Unwind::Return(value, self.span))
+    }
+}
+
+/// `break;` or `break label;`, ending the innermost loop or the loop with that label.
+#[derive(Debug, Clone)]
+pub struct BreakStatement {
+    pub(crate) label: Option<Rc<str>>,
+    pub(crate) span: Span
+}
+
+impl BreakStatement {
+    pub fn run(&self, _env: &mut Environment) -> Result<(), Unwind> {
+        Err(Unwind::Break(self.label.clone(), self.span))
+    }
+}
+
+/// `continue;` or `continue label;`, skipping to the next iteration of the
+/// innermost loop or the loop with that label.
+#[derive(Debug, Clone)]
+pub struct ContinueStatement {
+    pub(crate) label: Option<Rc<str>>,
+    pub(crate) span: Span
+}
+
+impl ContinueStatement {
+    pub fn run(&self, _env: &mut Environment) -> Result<(), Unwind> {
+        Err(Unwind::Continue(self.label.clone(), self.span))
+    }
+}
+
+/// What a loop does after running its body once.
+enum LoopFlow {
+    Next,
+    Exit
 }
 
-impl Statement for LetStatement {
-    fn run(&self) {
-        todo!()
+/// Decides how a loop labeled `label` carries on after its body finished with
+/// `result`. A `break` or `continue` aimed at some outer loop, like any other
+/// unwind, keeps travelling up.
+fn loop_flow(label: Option<&Rc<str>>, result: Result<DataType, Unwind>) -> Result<LoopFlow, Unwind> {
+    match result {
+        Ok(_) => Ok(LoopFlow::Next),
+        Err(Unwind::Break(target, _)) if target.is_none() || target.as_ref() == label => Ok(LoopFlow::Exit),
+        Err(Unwind::Continue(target, _)) if target.is_none() || target.as_ref() == label => Ok(LoopFlow::Next),
+        Err(unwind) => Err(unwind)
     }
-}
\ No newline at end of file
+}
+
+/// `while condition { ... }`. Iterations run in a plain Rust loop, so a long
+/// running loop takes no more stack than a single pass through its body.
+#[derive(Debug, Clone)]
+pub struct WhileStatement {
+    pub(crate) label: Option<Rc<str>>,
+    pub(crate) condition: Expr,
+    pub(crate) body: Block,
+    pub(crate) span: Span
+}
+
+impl WhileStatement {
+    pub fn run(&self, env: &mut Environment) -> Result<(), Unwind> {
+        loop {
+            match self.condition.value(env)? {
+                DataType::Boolean(true) => {}
+                DataType::Boolean(false) => return Ok(()),
+                other => {
+                    let kind = RuntimeErrorKind::NotABoolean { context: "as a `while` condition", found: other.type_name() };
+                    return Err(RuntimeError::new(kind, self.condition.span()).into())
+                }
+            }
+
+            if let LoopFlow::Exit = loop_flow(self.label.as_ref(), self.body.value(env))? {
+                return Ok(())
+            }
+        }
+    }
+}
+
+/// What a `for` loop walks over: `start..end` counts up from `start` to just
+/// below `end`, anything else has to evaluate to something iterable.
+#[derive(Debug, Clone)]
+pub enum Iterable {
+    Range { start: Expr, end: Expr },
+    Value(Expr)
+}
+
+/// `for item in iterable { ... }`. Each iteration gets a fresh scope holding
+/// the loop variable, so closures created in the body see their own `item`.
+#[derive(Debug, Clone)]
+pub struct ForStatement {
+    pub(crate) label: Option<Rc<str>>,
+    pub(crate) variable: Identifier,
+    pub(crate) iterable: Iterable,
+    pub(crate) body: Block,
+    pub(crate) span: Span
+}
+
+impl ForStatement {
+    pub fn run(&self, env: &mut Environment) -> Result<(), Unwind> {
+        match &self.iterable {
+            Iterable::Range { start, end } => {
+                let (from, to) = match (start.value(env)?, end.value(env)?) {
+                    (DataType::Number(NumberType::Integer(from)), DataType::Number(NumberType::Integer(to))) => (from, to),
+                    (from, to) => {
+                        let kind = RuntimeErrorKind::TypeMismatch { operation: "`..`", left: from.type_name(), right: to.type_name() };
+                        return Err(RuntimeError::new(kind, start.span().to(end.span())).into())
+                    }
+                };
+
+                for i in from..to {
+                    if let LoopFlow::Exit = self.iteration(env, DataType::Number(NumberType::Integer(i)))? {
+                        break;
+                    }
+                }
+            }
+            Iterable::Value(value) => match value.value(env)? {
+                DataType::List(items) => {
+                    // Indexing on every pass instead of holding a borrow lets
+                    // the body change the list it is walking over.
+                    let mut index = 0;
+                    loop {
+                        let item = items.borrow().get(index).cloned();
+                        let Some(item) = item else { break };
+                        index += 1;
+
+                        if let LoopFlow::Exit = self.iteration(env, item)? {
+                            break;
+                        }
+                    }
+                }
+                DataType::Map(map) => {
+                    // The keys are taken up front, so entries added by the
+                    // body are not visited. Removed ones are skipped.
+                    let keys: Vec<Rc<str>> = map.borrow().keys().cloned().collect();
+
+                    for key in keys {
+                        if !map.borrow().contains_key(&key) {
+                            continue;
+                        }
+                        if let LoopFlow::Exit = self.iteration(env, DataType::String(key))? {
+                            break;
+                        }
+                    }
+                }
+                other => {
+                    return Err(RuntimeError::new(RuntimeErrorKind::NotIterable(other.type_name()), value.span()).into())
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn iteration(&self, env: &mut Environment, item: DataType) -> Result<LoopFlow, Unwind> {
+        env.push_scope();
+        self.variable.declare(env, item, false);
+        let result = self.body.evaluate(env);
+        env.pop_scope();
+        loop_flow(self.label.as_ref(), result)
+    }
+}
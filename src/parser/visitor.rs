@@ -0,0 +1,265 @@
+use std::rc::Rc;
+use crate::parser::expression::{Block, Expr};
+use crate::parser::identifier::Identifier;
+use crate::parser::parser::Program;
+use crate::parser::statement::{AssignTarget, Iterable, Stmt};
+
+/// Walks a syntax tree without changing it. Every method defaults to visiting
+/// the children of its node, so an implementation only overrides the nodes it
+/// cares about and calls the matching `walk_` function to keep descending.
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program)
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt)
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr)
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block)
+    }
+
+    /// Called for every identifier read as an expression. Names being
+    /// declared or assigned to are part of their statement instead.
+    fn visit_identifier(&mut self, _identifier: &Identifier) {}
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for stmt in program.statements() {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match stmt {
+        Stmt::Let(s) => visitor.visit_expr(&s.value),
+        Stmt::Assign(s) => {
+            match &s.target {
+                AssignTarget::Variable(_) => {}
+                AssignTarget::Index(target) => {
+                    visitor.visit_expr(&target.target);
+                    visitor.visit_expr(&target.index);
+                }
+                AssignTarget::Field(target) => visitor.visit_expr(&target.target),
+            }
+            visitor.visit_expr(&s.value);
+        }
+        Stmt::Expression(s) => visitor.visit_expr(&s.expression),
+        Stmt::Return(s) => {
+            if let Some(value) = &s.value {
+                visitor.visit_expr(value);
+            }
+        }
+        Stmt::Break(_) | Stmt::Continue(_) => {}
+        Stmt::While(s) => {
+            visitor.visit_expr(&s.condition);
+            visitor.visit_block(&s.body);
+        }
+        Stmt::For(s) => {
+            match &s.iterable {
+                Iterable::Range { start, end } => {
+                    visitor.visit_expr(start);
+                    visitor.visit_expr(end);
+                }
+                Iterable::Value(value) => visitor.visit_expr(value),
+            }
+            visitor.visit_block(&s.body);
+        }
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        Expr::Literal(_) => {}
+        Expr::Identifier(identifier) => visitor.visit_identifier(identifier),
+        Expr::Binary(e) => {
+            visitor.visit_expr(&e.l);
+            visitor.visit_expr(&e.r);
+        }
+        Expr::Unary(e) => visitor.visit_expr(&e.inp),
+        Expr::Call(e) => {
+            visitor.visit_expr(&e.callee);
+            for argument in &e.arguments {
+                visitor.visit_expr(argument);
+            }
+        }
+        Expr::If(e) => {
+            visitor.visit_expr(&e.condition);
+            visitor.visit_block(&e.consequence);
+            if let Some(alternative) = &e.alternative {
+                visitor.visit_expr(alternative);
+            }
+        }
+        Expr::Function(function) => visitor.visit_block(&function.body),
+        Expr::Block(block) => visitor.visit_block(block),
+        Expr::List(list) => {
+            for item in &list.items {
+                visitor.visit_expr(item);
+            }
+        }
+        Expr::Index(e) => {
+            visitor.visit_expr(&e.target);
+            visitor.visit_expr(&e.index);
+        }
+        Expr::Slice(e) => {
+            visitor.visit_expr(&e.target);
+            if let Some(start) = &e.start {
+                visitor.visit_expr(start);
+            }
+            if let Some(end) = &e.end {
+                visitor.visit_expr(end);
+            }
+        }
+        Expr::Map(map) => {
+            for (_, value) in &map.entries {
+                visitor.visit_expr(value);
+            }
+        }
+        Expr::Field(e) => visitor.visit_expr(&e.target),
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &Block) {
+    for stmt in &block.statements {
+        visitor.visit_stmt(stmt);
+    }
+    if let Some(value) = &block.value {
+        visitor.visit_expr(value);
+    }
+}
+
+/// Like `Visitor`, but with mutable access to every node, for passes that
+/// rewrite the tree in place.
+///
+/// A function literal is shared with the function values created from it, so
+/// visiting its body copies the literal first if any such value is alive.
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program)
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        walk_stmt_mut(self, stmt)
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr)
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        walk_block_mut(self, block)
+    }
+
+    /// Called for every identifier read as an expression. Names being
+    /// declared or assigned to are part of their statement instead.
+    fn visit_identifier_mut(&mut self, _identifier: &mut Identifier) {}
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    for stmt in program.statements_mut() {
+        visitor.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut Stmt) {
+    match stmt {
+        Stmt::Let(s) => visitor.visit_expr_mut(&mut s.value),
+        Stmt::Assign(s) => {
+            match &mut s.target {
+                AssignTarget::Variable(_) => {}
+                AssignTarget::Index(target) => {
+                    visitor.visit_expr_mut(&mut target.target);
+                    visitor.visit_expr_mut(&mut target.index);
+                }
+                AssignTarget::Field(target) => visitor.visit_expr_mut(&mut target.target),
+            }
+            visitor.visit_expr_mut(&mut s.value);
+        }
+        Stmt::Expression(s) => visitor.visit_expr_mut(&mut s.expression),
+        Stmt::Return(s) => {
+            if let Some(value) = &mut s.value {
+                visitor.visit_expr_mut(value);
+            }
+        }
+        Stmt::Break(_) | Stmt::Continue(_) => {}
+        Stmt::While(s) => {
+            visitor.visit_expr_mut(&mut s.condition);
+            visitor.visit_block_mut(&mut s.body);
+        }
+        Stmt::For(s) => {
+            match &mut s.iterable {
+                Iterable::Range { start, end } => {
+                    visitor.visit_expr_mut(start);
+                    visitor.visit_expr_mut(end);
+                }
+                Iterable::Value(value) => visitor.visit_expr_mut(value),
+            }
+            visitor.visit_block_mut(&mut s.body);
+        }
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expr) {
+    match expr {
+        Expr::Literal(_) => {}
+        Expr::Identifier(identifier) => visitor.visit_identifier_mut(identifier),
+        Expr::Binary(e) => {
+            visitor.visit_expr_mut(&mut e.l);
+            visitor.visit_expr_mut(&mut e.r);
+        }
+        Expr::Unary(e) => visitor.visit_expr_mut(&mut e.inp),
+        Expr::Call(e) => {
+            visitor.visit_expr_mut(&mut e.callee);
+            for argument in &mut e.arguments {
+                visitor.visit_expr_mut(argument);
+            }
+        }
+        Expr::If(e) => {
+            visitor.visit_expr_mut(&mut e.condition);
+            visitor.visit_block_mut(&mut e.consequence);
+            if let Some(alternative) = &mut e.alternative {
+                visitor.visit_expr_mut(alternative);
+            }
+        }
+        Expr::Function(function) => visitor.visit_block_mut(&mut Rc::make_mut(function).body),
+        Expr::Block(block) => visitor.visit_block_mut(block),
+        Expr::List(list) => {
+            for item in &mut list.items {
+                visitor.visit_expr_mut(item);
+            }
+        }
+        Expr::Index(e) => {
+            visitor.visit_expr_mut(&mut e.target);
+            visitor.visit_expr_mut(&mut e.index);
+        }
+        Expr::Slice(e) => {
+            visitor.visit_expr_mut(&mut e.target);
+            if let Some(start) = &mut e.start {
+                visitor.visit_expr_mut(start);
+            }
+            if let Some(end) = &mut e.end {
+                visitor.visit_expr_mut(end);
+            }
+        }
+        Expr::Map(map) => {
+            for (_, value) in &mut map.entries {
+                visitor.visit_expr_mut(value);
+            }
+        }
+        Expr::Field(e) => visitor.visit_expr_mut(&mut e.target),
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, block: &mut Block) {
+    for stmt in &mut block.statements {
+        visitor.visit_stmt_mut(stmt);
+    }
+    if let Some(value) = &mut block.value {
+        visitor.visit_expr_mut(value);
+    }
+}
@@ -0,0 +1,51 @@
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+use crate::lexer::span::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    Undeclared(Rc<str>),
+    /// A global read by top level code before the `let` that declares it.
+    UsedBeforeDeclaration(Rc<str>),
+    /// A second `let` of a name in the same scope, which replaces the first.
+    Duplicate { name: Rc<str>, previous: Span },
+    /// A local that is never read. Names starting with `_` are not reported.
+    Unused(Rc<str>),
+}
+
+impl Display for DiagnosticKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiagnosticKind::Undeclared(name) => write!(f, "variable `{}` used without declaration", name),
+            DiagnosticKind::UsedBeforeDeclaration(name) => write!(f, "variable `{}` used before its declaration", name),
+            DiagnosticKind::Duplicate { name, previous } => {
+                write!(f, "`{}` is already declared in this scope, at {}", name, previous)
+            }
+            DiagnosticKind::Unused(name) => write!(f, "variable `{}` is never used", name),
+        }
+    }
+}
+
+/// Something the resolver found wrong with a program before running it.
+/// Errors would fail at runtime; warnings are likely mistakes that run fine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    pub fn new(kind: DiagnosticKind, span: Span) -> Diagnostic {
+        Diagnostic { kind, span }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self.kind, DiagnosticKind::Undeclared(_) | DiagnosticKind::UsedBeforeDeclaration(_))
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span, self.kind)
+    }
+}
@@ -0,0 +1,3 @@
+#[allow(clippy::module_inception)]
+pub mod resolver;
+pub mod diagnostic;
\ No newline at end of file
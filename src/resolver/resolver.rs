@@ -0,0 +1,192 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use crate::builtins::builtins::BUILTINS;
+use crate::lexer::span::Span;
+use crate::parser::expression::{Block, Expr};
+use crate::parser::identifier::Identifier;
+use crate::parser::parser::Program;
+use crate::parser::statement::{AssignTarget, Iterable, Stmt};
+use crate::parser::visitor::{walk_block_mut, walk_expr_mut, walk_stmt_mut, VisitorMut};
+use crate::resolver::diagnostic::{Diagnostic, DiagnosticKind};
+
+/// Resolves every local variable of `program` to the scope and slot it lives
+/// in at runtime, and returns what it found wrong on the way, in source order.
+///
+/// Scopes follow the interpreter's: a block gets one, while a call and a pass
+/// through a `for` loop get one shared by the parameters or loop variable and
+/// the body. Globals are left to be looked up by name, since a function may
+/// use one declared after it, and a later run may use one from an earlier run.
+///
+/// A function can only use locals declared before it, as when it runs, apart
+/// from the one it is being assigned to, which lets it call itself.
+pub fn resolve(program: &mut Program) -> Vec<Diagnostic> {
+    let mut resolver = Resolver::new(program);
+    resolver.visit_program_mut(program);
+    resolver.diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+    resolver.diagnostics
+}
+
+struct Local {
+    name: Rc<str>,
+    slot: usize,
+    span: Span,
+    used: bool,
+}
+
+#[derive(Default)]
+struct Scope {
+    locals: Vec<Local>,
+    /// Locals replaced by a later `let` of the same name, kept to check they were used.
+    replaced: Vec<Local>,
+    slots: usize,
+}
+
+struct Resolver {
+    /// The local scopes, innermost last. The top level is not one of them.
+    scopes: Vec<Scope>,
+    /// How many function bodies the resolver is inside.
+    functions: usize,
+    /// Globals declared so far, and every global the program declares.
+    globals: HashMap<Rc<str>, Span>,
+    all_globals: HashSet<Rc<str>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Resolver {
+    fn new(program: &Program) -> Resolver {
+        let all_globals = program.statements().iter()
+            .filter_map(|statement| match statement {
+                Stmt::Let(s) => Some(Rc::clone(&s.identifier.name)),
+                _ => None
+            })
+            .collect();
+        Resolver { scopes: vec![], functions: 0, globals: HashMap::new(), all_globals, diagnostics: vec![] }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().expect("scopes are balanced");
+        for local in scope.replaced.into_iter().chain(scope.locals) {
+            if !local.used && !local.name.starts_with('_') {
+                self.diagnostics.push(Diagnostic::new(DiagnosticKind::Unused(local.name), local.span));
+            }
+        }
+    }
+
+    /// Declares `identifier` in the innermost scope. Declaring a name twice
+    /// in one scope reuses its slot, as the second binding replaces the first.
+    fn declare(&mut self, identifier: &mut Identifier) {
+        let name = Rc::clone(&identifier.name);
+        let Some(scope) = self.scopes.last_mut() else {
+            if let Some(previous) = self.globals.insert(name.clone(), identifier.span) {
+                self.diagnostics.push(Diagnostic::new(DiagnosticKind::Duplicate { name, previous }, identifier.span));
+            }
+            return;
+        };
+
+        let slot = match scope.locals.iter().position(|local| local.name == name) {
+            Some(index) => {
+                let previous = scope.locals.remove(index);
+                let slot = previous.slot;
+                let kind = DiagnosticKind::Duplicate { name: Rc::clone(&name), previous: previous.span };
+                self.diagnostics.push(Diagnostic::new(kind, identifier.span));
+                scope.replaced.push(previous);
+                slot
+            }
+            None => {
+                scope.slots += 1;
+                scope.slots - 1
+            }
+        };
+        scope.locals.push(Local { name, slot, span: identifier.span, used: false });
+        identifier.resolved = Some((0, slot));
+    }
+
+    /// Resolves a use of `identifier`, which only counts as reading it if `read` is set.
+    fn reference(&mut self, identifier: &mut Identifier, read: bool) {
+        for (depth, scope) in self.scopes.iter_mut().rev().enumerate() {
+            if let Some(local) = scope.locals.iter_mut().find(|local| local.name == identifier.name) {
+                local.used |= read;
+                identifier.resolved = Some((depth, local.slot));
+                return;
+            }
+        }
+
+        let name = &identifier.name;
+        if self.globals.contains_key(name) || BUILTINS.iter().any(|builtin| builtin.name == &**name) {
+            return;
+        }
+        let kind = match self.all_globals.contains(name) {
+            true if self.functions > 0 => return,
+            true => DiagnosticKind::UsedBeforeDeclaration(Rc::clone(name)),
+            false => DiagnosticKind::Undeclared(Rc::clone(name)),
+        };
+        self.diagnostics.push(Diagnostic::new(kind, identifier.span));
+    }
+}
+
+impl VisitorMut for Resolver {
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::Let(s) => {
+                if let Expr::Function(_) = s.value {
+                    self.declare(&mut s.identifier);
+                    self.visit_expr_mut(&mut s.value);
+                } else {
+                    self.visit_expr_mut(&mut s.value);
+                    self.declare(&mut s.identifier);
+                }
+            }
+            Stmt::Assign(s) => {
+                if let AssignTarget::Variable(variable) = &mut s.target {
+                    self.reference(variable, s.op.is_some());
+                }
+                walk_stmt_mut(self, stmt);
+            }
+            Stmt::For(s) => {
+                match &mut s.iterable {
+                    Iterable::Range { start, end } => {
+                        self.visit_expr_mut(start);
+                        self.visit_expr_mut(end);
+                    }
+                    Iterable::Value(value) => self.visit_expr_mut(value),
+                }
+                self.begin_scope();
+                self.declare(&mut s.variable);
+                walk_block_mut(self, &mut s.body);
+                self.end_scope();
+            }
+            _ => walk_stmt_mut(self, stmt),
+        }
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Function(function) => {
+                let function = Rc::make_mut(function);
+                self.functions += 1;
+                self.begin_scope();
+                for parameter in &mut function.parameters {
+                    self.declare(parameter);
+                }
+                walk_block_mut(self, &mut function.body);
+                self.end_scope();
+                self.functions -= 1;
+            }
+            _ => walk_expr_mut(self, expr),
+        }
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        self.begin_scope();
+        walk_block_mut(self, block);
+        self.end_scope();
+    }
+
+    fn visit_identifier_mut(&mut self, identifier: &mut Identifier) {
+        self.reference(identifier, true);
+    }
+}
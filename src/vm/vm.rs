@@ -0,0 +1,474 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use crate::builtins::builtins::{Builtin, BUILTINS};
+use crate::compiler::bytecode::{Bytecode, Capture, Condition, Instruction};
+use crate::datatypes::datatypes::{DataType, NumberType};
+use crate::datatypes::function::{Closure, MAX_CALL_DEPTH};
+use crate::datatypes::map::Map;
+use crate::interpreter::error::{RuntimeError, RuntimeErrorKind};
+use crate::operators::operators::BinaryOperator;
+
+/// A global slot. Slots are made for every name a program could refer to,
+/// so one may not have been defined yet.
+struct Global {
+    name: Rc<str>,
+    value: Option<DataType>,
+    mutable: bool,
+}
+
+/// The globals of a `Vm`, numbered so compiled code can reach them without
+/// hashing their names.
+#[derive(Default)]
+struct Globals {
+    slots: Vec<Global>,
+    indices: HashMap<Rc<str>, u32>,
+    /// For every bytecode run so far that is still in use, the slot each of
+    /// its name constants refers to, by the bytecode's address. The weak
+    /// reference keeps the address from being reused while the entry is here.
+    links: HashMap<*const Bytecode, (Weak<Bytecode>, Rc<[u32]>)>,
+}
+
+impl Globals {
+    fn slot(&mut self, name: &Rc<str>) -> u32 {
+        if let Some(&index) = self.indices.get(name) {
+            return index;
+        }
+        let index = self.slots.len() as u32;
+        self.slots.push(Global { name: Rc::clone(name), value: None, mutable: false });
+        self.indices.insert(Rc::clone(name), index);
+        index
+    }
+
+    /// The global slots of `bytecode`'s constants, indexed like the constants.
+    /// Constants that are not strings get no slot.
+    fn link(&mut self, bytecode: &Rc<Bytecode>) -> Rc<[u32]> {
+        if let Some((_, links)) = self.links.get(&Rc::as_ptr(bytecode)) {
+            return Rc::clone(links);
+        }
+        let links: Rc<[u32]> = bytecode.constants.iter()
+            .map(|constant| match constant {
+                DataType::String(name) => self.slot(name),
+                _ => u32::MAX,
+            })
+            .collect();
+        self.links.insert(Rc::as_ptr(bytecode), (Rc::downgrade(bytecode), Rc::clone(&links)));
+        links
+    }
+
+    /// Drops the links of bytecode nothing uses any more.
+    fn unlink_dropped(&mut self) {
+        self.links.retain(|_, (bytecode, _)| bytecode.strong_count() > 0);
+    }
+}
+
+/// What a `for` loop is walking over, mirroring the interpreter: a list is
+/// indexed afresh on every pass, a map's keys are taken up front and each is
+/// skipped if it has been removed by the time the loop gets to it.
+enum Iteration {
+    Range(std::ops::Range<i64>),
+    List { items: Rc<RefCell<Vec<DataType>>>, index: usize },
+    Keys { map: Rc<RefCell<Map>>, keys: std::vec::IntoIter<Rc<str>> },
+}
+
+impl Iteration {
+    fn next(&mut self) -> Option<DataType> {
+        match self {
+            Iteration::Range(range) => range.next().map(|i| DataType::Number(NumberType::Integer(i))),
+            Iteration::List { items, index } => {
+                let item = items.borrow().get(*index).cloned();
+                *index += 1;
+                item
+            }
+            Iteration::Keys { map, keys } => keys.find(|key| map.borrow().contains_key(key)).map(DataType::String),
+        }
+    }
+}
+
+/// A local slot. A local starts out holding its value directly and is moved
+/// into a shared cell the first time a closure captures it.
+enum Slot {
+    Value(DataType),
+    Cell(Rc<RefCell<DataType>>),
+}
+
+impl Slot {
+    fn get(&self) -> DataType {
+        match self {
+            Slot::Value(value) => value.clone(),
+            Slot::Cell(cell) => cell.borrow().clone(),
+        }
+    }
+
+    fn set(&mut self, value: DataType) {
+        match self {
+            Slot::Cell(cell) => *cell.borrow_mut() = value,
+            Slot::Value(slot) => *slot = value,
+        }
+    }
+
+    fn capture(&mut self) -> Rc<RefCell<DataType>> {
+        if let Slot::Cell(cell) = self {
+            return Rc::clone(cell);
+        }
+        let value = self.get();
+        let cell = Rc::new(RefCell::new(value));
+        *self = Slot::Cell(Rc::clone(&cell));
+        cell
+    }
+}
+
+/// A function call in progress.
+struct Frame {
+    closure: Rc<Closure>,
+    /// The global slots of the closure's bytecode, from `Globals::link`.
+    links: Rc<[u32]>,
+    ip: usize,
+    locals: Vec<Slot>,
+    iterators: Vec<Iteration>,
+    /// The height of the operand stack when the call started.
+    base: usize,
+}
+
+impl Frame {
+    fn new(closure: Rc<Closure>, links: Rc<[u32]>, base: usize) -> Frame {
+        let slots = closure.prototype().slots as usize;
+        let locals = (0..slots).map(|_| Slot::Value(DataType::Nil)).collect();
+        // Until a loop starts, its slot holds an iterator that is already done.
+        let iterators = (0..closure.prototype().iterators).map(|_| Iteration::Range(0..0)).collect();
+        Frame { closure, links, ip: 0, locals, iterators, base }
+    }
+}
+
+fn pop(stack: &mut Vec<DataType>) -> DataType {
+    stack.pop().expect("the compiler keeps the operand stack balanced")
+}
+
+fn name(bytecode: &Bytecode, index: u32) -> &Rc<str> {
+    match &bytecode.constants[index as usize] {
+        DataType::String(name) => name,
+        other => unreachable!("name constant {} is a {}", index, other.type_name()),
+    }
+}
+
+/// `a op b` for two integers, without going through `DataType::binary_operation`.
+/// Anything that fails, like an overflow, is left to it to report.
+fn integer_operation(op: BinaryOperator, a: i64, b: i64) -> Option<DataType> {
+    let number = |result: Option<i64>| result.map(|v| DataType::Number(NumberType::Integer(v)));
+    match op {
+        BinaryOperator::Add => number(a.checked_add(b)),
+        BinaryOperator::Subtract => number(a.checked_sub(b)),
+        BinaryOperator::Multiply => number(a.checked_mul(b)),
+        BinaryOperator::Divide => number(a.checked_div(b)),
+        BinaryOperator::Remainder => number(a.checked_rem(b)),
+        BinaryOperator::Equal => Some(DataType::Boolean(a == b)),
+        BinaryOperator::NotEqual => Some(DataType::Boolean(a != b)),
+        BinaryOperator::LessThan => Some(DataType::Boolean(a < b)),
+        BinaryOperator::GreaterThan => Some(DataType::Boolean(a > b)),
+        BinaryOperator::LessThanOrEqual => Some(DataType::Boolean(a <= b)),
+        BinaryOperator::GreaterThanOrEqual => Some(DataType::Boolean(a >= b)),
+        BinaryOperator::Or | BinaryOperator::And => None,
+    }
+}
+
+/// Runs bytecode made by the `compiler`. Like the `Interpreter`, it keeps
+/// the globals made by one run around for the next, and builtins can be
+/// shadowed by globals of the same name.
+///
+/// Script calls don't recurse on the host stack; every call pushes a frame
+/// and the same loop carries on executing it.
+pub struct Vm {
+    globals: Globals,
+    builtins: HashMap<&'static str, Builtin>,
+    stack: Vec<DataType>,
+    frames: Vec<Frame>,
+}
+
+impl Vm {
+    pub fn new() -> Vm {
+        Vm {
+            globals: Globals::default(),
+            builtins: BUILTINS.iter().map(|builtin| (builtin.name, *builtin)).collect(),
+            stack: Vec::new(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Runs the top level of `bytecode`, stopping at the first runtime error.
+    /// Globals defined before the error are kept.
+    pub fn run(&mut self, bytecode: &Rc<Bytecode>) -> Result<(), RuntimeError> {
+        let main = Rc::new(Closure { bytecode: Rc::clone(bytecode), function: 0, upvalues: Vec::new() });
+        self.globals.unlink_dropped();
+        let links = self.globals.link(bytecode);
+        self.frames.push(Frame::new(main, links, 0));
+
+        let result = self.execute();
+        self.frames.clear();
+        self.stack.clear();
+        result
+    }
+
+    pub fn get(&self, name: &str) -> Option<DataType> {
+        let global = self.globals.indices.get(name).and_then(|&index| self.globals.slots[index as usize].value.clone());
+        global.or_else(|| self.builtins.get(name).map(|builtin| DataType::Builtin(*builtin)))
+    }
+
+    /// All globals defined so far, sorted by name so the output is stable.
+    pub fn bindings(&self) -> Vec<(Rc<str>, DataType)> {
+        let mut bindings: Vec<(Rc<str>, DataType)> = self.globals.slots.iter()
+            .filter_map(|global| Some((Rc::clone(&global.name), global.value.clone()?)))
+            .collect();
+        bindings.sort_by(|a, b| a.0.cmp(&b.0));
+        bindings
+    }
+
+    fn execute(&mut self) -> Result<(), RuntimeError> {
+        loop {
+            let frame = self.frames.last_mut().expect("the top level runs until it returns");
+            let instruction = frame.closure.prototype().code[frame.ip];
+            frame.ip += 1;
+
+            match self.step(instruction) {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(kind) => {
+                    let frame = self.frames.last().expect("the failing frame is still running");
+                    let span = frame.closure.prototype().spans[frame.ip - 1];
+                    return Err(RuntimeError::new(kind, span));
+                }
+            }
+        }
+    }
+
+    /// Executes one instruction of the innermost frame, returning whether
+    /// there is more to run.
+    #[inline(always)]
+    fn step(&mut self, instruction: Instruction) -> Result<bool, RuntimeErrorKind> {
+        let Vm { globals, builtins, stack, frames } = self;
+        let frame = frames.last_mut().expect("the top level runs until it returns");
+
+        match instruction {
+            Instruction::Constant(index) => stack.push(frame.closure.bytecode.constants[index as usize].clone()),
+            Instruction::Nil => stack.push(DataType::Nil),
+            Instruction::Pop => { pop(stack); }
+            Instruction::PopN(count) => stack.truncate(stack.len() - count as usize),
+            Instruction::Pick(depth) => stack.push(stack[stack.len() - 1 - depth as usize].clone()),
+            Instruction::Swap => {
+                let len = stack.len();
+                stack.swap(len - 1, len - 2);
+            }
+
+            Instruction::GetLocal(slot) => stack.push(frame.locals[slot as usize].get()),
+            Instruction::SetLocal(slot) => frame.locals[slot as usize].set(pop(stack)),
+            Instruction::DeclareLocal(slot) => frame.locals[slot as usize] = Slot::Value(pop(stack)),
+            Instruction::GetUpvalue(index) => stack.push(frame.closure.upvalues[index as usize].borrow().clone()),
+            Instruction::SetUpvalue(index) => *frame.closure.upvalues[index as usize].borrow_mut() = pop(stack),
+            Instruction::GetGlobal(index) => {
+                let global = &globals.slots[frame.links[index as usize] as usize];
+                let value = match &global.value {
+                    Some(value) => value.clone(),
+                    None => match builtins.get(&*global.name) {
+                        Some(builtin) => DataType::Builtin(*builtin),
+                        None => return Err(RuntimeErrorKind::UndefinedVariable(Rc::clone(&global.name)))
+                    }
+                };
+                stack.push(value);
+            }
+            Instruction::SetGlobal(index) => {
+                let global = &mut globals.slots[frame.links[index as usize] as usize];
+                match &mut global.value {
+                    Some(value) if global.mutable => *value = pop(stack),
+                    Some(_) => return Err(RuntimeErrorKind::ImmutableAssignment(Rc::clone(&global.name))),
+                    None if builtins.contains_key(&*global.name) => return Err(RuntimeErrorKind::ImmutableAssignment(Rc::clone(&global.name))),
+                    None => return Err(RuntimeErrorKind::UndeclaredAssignment(Rc::clone(&global.name))),
+                }
+            }
+            Instruction::DefineGlobal { name, mutable } => {
+                let global = &mut globals.slots[frame.links[name as usize] as usize];
+                global.value = Some(pop(stack));
+                global.mutable = mutable;
+            }
+
+            Instruction::Binary(op) => {
+                let other = pop(stack);
+                let one = stack.last_mut().expect("the compiler keeps the operand stack balanced");
+                *one = match (&*one, &other) {
+                    (DataType::Number(NumberType::Integer(a)), DataType::Number(NumberType::Integer(b))) => {
+                        match integer_operation(op, *a, *b) {
+                            Some(result) => result,
+                            None => one.binary_operation(op, other)?,
+                        }
+                    }
+                    _ => one.binary_operation(op, other)?,
+                };
+            }
+            Instruction::Unary(op) => {
+                let operand = pop(stack);
+                stack.push(operand.unary_operation(op)?);
+            }
+            Instruction::CheckBoolean(condition) => match stack.last() {
+                Some(DataType::Boolean(_)) => {}
+                other => {
+                    let found = other.map_or("nil", DataType::type_name);
+                    return Err(RuntimeErrorKind::NotABoolean { context: condition.context(), found });
+                }
+            },
+            Instruction::Jump(target) => frame.ip = target as usize,
+            Instruction::JumpIfFalse(target, condition) => match pop(stack) {
+                DataType::Boolean(true) => {}
+                DataType::Boolean(false) => frame.ip = target as usize,
+                other => return Err(RuntimeErrorKind::NotABoolean { context: condition.context(), found: other.type_name() }),
+            },
+            Instruction::Or(target) | Instruction::And(target) => {
+                let (stop_at, condition) = match instruction {
+                    Instruction::Or(_) => (true, Condition::Or),
+                    _ => (false, Condition::And),
+                };
+                match pop(stack) {
+                    DataType::Boolean(value) if value == stop_at => {
+                        stack.push(DataType::Boolean(value));
+                        frame.ip = target as usize;
+                    }
+                    DataType::Boolean(_) => {}
+                    other => return Err(RuntimeErrorKind::NotABoolean { context: condition.context(), found: other.type_name() }),
+                }
+            }
+
+            Instruction::Call(count) => {
+                let callee_at = stack.len() - count as usize - 1;
+                match stack[callee_at].clone() {
+                    DataType::Builtin(builtin) => {
+                        let result = builtin.call(&stack[callee_at + 1..])?;
+                        stack.truncate(callee_at);
+                        stack.push(result);
+                    }
+                    DataType::Closure(closure) => {
+                        let arity = closure.prototype().arity;
+                        if count != arity {
+                            return Err(RuntimeErrorKind::ArgumentCountMismatch {
+                                function: closure.display_name(),
+                                expected: arity as usize,
+                                found: count as usize
+                            });
+                        }
+                        let links = if Rc::ptr_eq(&closure.bytecode, &frame.closure.bytecode) {
+                            Rc::clone(&frame.links)
+                        } else {
+                            globals.link(&closure.bytecode)
+                        };
+                        // The top level's frame is not a call.
+                        if frames.len() > MAX_CALL_DEPTH {
+                            return Err(RuntimeErrorKind::StackOverflow { depth: MAX_CALL_DEPTH });
+                        }
+
+                        let mut callee = Frame::new(closure, links, callee_at);
+                        for (slot, argument) in callee.locals.iter_mut().zip(stack.drain(callee_at + 1..)) {
+                            *slot = Slot::Value(argument);
+                        }
+                        stack.truncate(callee_at);
+                        frames.push(callee);
+                    }
+                    other => return Err(RuntimeErrorKind::NotCallable(other.type_name())),
+                }
+            }
+            Instruction::Closure(index) => {
+                let bytecode = Rc::clone(&frame.closure.bytecode);
+                let upvalues = bytecode.functions[index as usize].captures.iter()
+                    .map(|capture| match *capture {
+                        Capture::Local(slot) => frame.locals[slot as usize].capture(),
+                        Capture::Upvalue(index) => Rc::clone(&frame.closure.upvalues[index as usize]),
+                    })
+                    .collect();
+                stack.push(DataType::Closure(Rc::new(Closure { bytecode, function: index as usize, upvalues })));
+            }
+            Instruction::Return => {
+                let value = pop(stack);
+                let finished = frames.pop().expect("the returning frame is running");
+                stack.truncate(finished.base);
+                if frames.is_empty() {
+                    return Ok(false);
+                }
+                stack.push(value);
+            }
+
+            Instruction::List(count) => {
+                let items = stack.split_off(stack.len() - count as usize);
+                stack.push(DataType::new_list(items));
+            }
+            Instruction::Map => stack.push(DataType::new_map(Map::new())),
+            Instruction::MapEntry(key) => {
+                let value = pop(stack);
+                let map = pop(stack);
+                map.set_index(&frame.closure.bytecode.constants[key as usize], value)?;
+                stack.push(map);
+            }
+            Instruction::Index => {
+                let index = pop(stack);
+                let target = pop(stack);
+                stack.push(target.index(&index)?);
+            }
+            Instruction::SetIndex => {
+                let value = pop(stack);
+                let index = pop(stack);
+                let container = pop(stack);
+                container.set_index(&index, value)?;
+            }
+            Instruction::Slice { start, end } => {
+                let end = if end { Some(pop(stack)) } else { None };
+                let start = if start { Some(pop(stack)) } else { None };
+                let target = pop(stack);
+                stack.push(target.slice(start.as_ref(), end.as_ref())?);
+            }
+
+            Instruction::RangeIterator(iterator) => {
+                let end = pop(stack);
+                let start = pop(stack);
+                let range = match (start, end) {
+                    (DataType::Number(NumberType::Integer(from)), DataType::Number(NumberType::Integer(to))) => from..to,
+                    (from, to) => {
+                        return Err(RuntimeErrorKind::TypeMismatch { operation: "`..`", left: from.type_name(), right: to.type_name() })
+                    }
+                };
+                frame.iterators[iterator as usize] = Iteration::Range(range);
+            }
+            Instruction::ValueIterator(iterator) => {
+                let iteration = match pop(stack) {
+                    DataType::List(items) => Iteration::List { items, index: 0 },
+                    DataType::Map(map) => {
+                        let keys: Vec<Rc<str>> = map.borrow().keys().cloned().collect();
+                        Iteration::Keys { map, keys: keys.into_iter() }
+                    }
+                    other => return Err(RuntimeErrorKind::NotIterable(other.type_name())),
+                };
+                frame.iterators[iterator as usize] = iteration;
+            }
+            Instruction::IterateNext { iterator, done } => {
+                match frame.iterators[iterator as usize].next() {
+                    Some(item) => stack.push(item),
+                    None => frame.ip = done as usize,
+                }
+            }
+
+            Instruction::ImmutableAssignment(index) => {
+                return Err(RuntimeErrorKind::ImmutableAssignment(Rc::clone(name(&frame.closure.bytecode, index))))
+            }
+            Instruction::ReturnOutsideFunction => return Err(RuntimeErrorKind::ReturnOutsideFunction),
+            Instruction::BreakOutsideLoop(label) => {
+                let label = label.map(|index| Rc::clone(name(&frame.closure.bytecode, index)));
+                return Err(RuntimeErrorKind::LoopControlOutsideLoop { keyword: "break", label })
+            }
+            Instruction::ContinueOutsideLoop(label) => {
+                let label = label.map(|index| Rc::clone(name(&frame.closure.bytecode, index)));
+                return Err(RuntimeErrorKind::LoopControlOutsideLoop { keyword: "continue", label })
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Vm::new()
+    }
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use std::rc::Rc;
+use scriptx::compiler::compiler::compile;
+use scriptx::interpreter::error::{RuntimeError, RuntimeErrorKind};
+use scriptx::interpreter::interpreter::Interpreter;
+use scriptx::vm::vm::Vm;
+use common::{error, eval, int, program};
+
+/// The error each engine stops `source` with.
+fn errors(source: &str) -> (RuntimeError, RuntimeError) {
+    let program = program(source);
+    let walked = Interpreter::new().run(&program).expect_err("the interpreter ran to the end");
+    let executed = Vm::new().run(&Rc::new(compile(&program))).expect_err("the vm ran to the end");
+    (walked, executed)
+}
+
+#[test]
+fn assignment_updates_mutable_bindings() {
+    assert_eq!(eval("{ let mut a = 1; a = a + 1; a }"), int(2));
+    assert_eq!(eval("{ let mut a = 10; a += 2; a -= 4; a *= 3; a /= 8; a }"), int(3));
+    assert_eq!(eval("{ let mut a = 1; let b = { a = 5; a + 1 }; [a, b] }"), eval("[5, 6]"));
+    assert_eq!(eval("{ let xs = [1, 2]; xs[0] += 5; let m = {\"k\": 1}; m.k *= 3; [xs, m.k] }"), eval("[[6, 2], 3]"));
+    assert_eq!(eval("{ let f = fn() { a = 2; }; f() }"), error("1:31: cannot assign to `a`, which was never declared"));
+}
+
+#[test]
+fn assigning_to_an_immutable_let_is_an_error() {
+    assert_eq!(eval("{ let a = 1; a = 2; a }"), error("1:27: cannot assign to immutable variable `a`; declare it with `let mut` to allow this"));
+    assert_eq!(eval("{ let a = 1; a += 2; a }"), error("1:27: cannot assign to immutable variable `a`; declare it with `let mut` to allow this"));
+    assert_eq!(eval("{ let mut a = 1; { let a = 2; a = 3; } a }"), error("1:44: cannot assign to immutable variable `a`; declare it with `let mut` to allow this"));
+
+    // The span is the name being assigned to.
+    let source = "let a = 1;\nlet f = fn() {\n    a = 2;\n};\nf();";
+    let (walked, executed) = errors(source);
+    for error in [walked, executed] {
+        assert_eq!(error.kind, RuntimeErrorKind::ImmutableAssignment(Rc::from("a")));
+        assert_eq!((error.span.start, error.span.end, error.span.line, error.span.column), (30, 31, 3, 5));
+    }
+}
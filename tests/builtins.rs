@@ -0,0 +1,127 @@
+mod common;
+
+use common::{boolean, error, eval, string};
+
+#[test]
+fn trim_upper_and_lower() {
+    assert_eq!(eval(r#"trim("  a b \t\n")"#), string("a b"));
+    assert_eq!(eval(r#"trim("")"#), string(""));
+    assert_eq!(eval(r#"upper("straße é")"#), string("STRASSE É"));
+    assert_eq!(eval(r#"lower("ÀB c")"#), string("àb c"));
+    assert_eq!(eval("trim(1)"), error("1:14: invalid argument to `trim`: expected a string for argument 1, found integer"));
+    assert_eq!(eval("upper([])"), error("1:14: invalid argument to `upper`: expected a string for argument 1, found list"));
+}
+
+#[test]
+fn contains_and_replace() {
+    assert_eq!(eval(r#"contains("hello", "ell")"#), boolean(true));
+    assert_eq!(eval(r#"contains("hello", "")"#), boolean(true));
+    assert_eq!(eval(r#"contains("hello", "xyz")"#), boolean(false));
+    assert_eq!(eval(r#"contains("hello", 1)"#), error("1:14: invalid argument to `contains`: expected a string for argument 2, found integer"));
+    assert_eq!(eval(r#"replace("a-b-c", "-", "+")"#), string("a+b+c"));
+    assert_eq!(eval(r#"replace("aaa", "aa", "b")"#), string("ba"));
+    assert_eq!(eval(r#"replace("abc", "x", "y")"#), string("abc"));
+    assert_eq!(eval(r#"replace("abc", "", "y")"#), error("1:14: invalid argument to `replace`: the pattern must not be empty"));
+    assert_eq!(eval(r#"replace("abc", "a", 1)"#), error("1:14: invalid argument to `replace`: expected a string for argument 3, found integer"));
+}
+
+#[test]
+fn slicing_strings() {
+    assert_eq!(eval(r#""héllo"[1..3]"#), string("él"));
+    assert_eq!(eval(r#""héllo"[..2]"#), string("hé"));
+    assert_eq!(eval(r#""héllo"[3..]"#), string("lo"));
+    assert_eq!(eval(r#""héllo"[-2..]"#), string("lo"));
+    assert_eq!(eval(r#""abc"[2..2]"#), string(""));
+    assert_eq!(eval(r#"slice("😀ab", 0, 2)"#), string("😀a"));
+    assert_eq!(eval(r#""abc"[0..4]"#), error("1:14: index 4 is out of bounds for length 3"));
+    assert_eq!(eval(r#""abc"[2..1]"#), error("1:14: slice starts at 2 but ends at 1"));
+    assert_eq!(eval(r#""abc"["a"..]"#), error("1:14: can't index into a string with a value of type string"));
+    assert_eq!(eval(r#"slice("abc", 0, 4)"#), error("1:14: index 4 is out of bounds for length 3"));
+    assert_eq!(eval(r#"slice("abc", 2, 1)"#), error("1:14: invalid argument to `slice`: start 2 is after end 1"));
+}
+
+#[test]
+fn sorting_orders_integers_and_floats_exactly() {
+    // 2^53 + 1 has no float of its own, so it has to sort after 2^53 as a float.
+    assert_eq!(
+        eval("{ let xs = [9007199254740993, 2, 9007199254740992.0, 1.5, 9007199254740992, 1]; sort(xs); xs }"),
+        eval("[1, 1.5, 2, 9007199254740992.0, 9007199254740992, 9007199254740993]")
+    );
+    assert_eq!(eval("{ let xs = [-0.5, 0, -1, 0.5]; sort(xs); xs }"), eval("[-1, -0.5, 0, 0.5]"));
+
+    // Enough items that an inconsistent order would upset the sort itself.
+    let source = "{
+        let xs = [];
+        for i in 0..200 {
+            push(xs, 9007199254740992 + i % 3);
+            push(xs, 9007199254740992.0 + (i % 5) * 2);
+        }
+        sort(xs);
+        [xs[0], xs[399]]
+    }";
+    assert_eq!(eval(source), eval("[9007199254740992, 9007199254741000.0]"));
+}
+
+#[test]
+fn reverse_and_pop() {
+    assert_eq!(eval("{ let xs = [1, \"a\", [2]]; reverse(xs); xs }"), eval("[[2], \"a\", 1]"));
+    assert_eq!(eval("{ let xs = []; reverse(xs); xs }"), eval("[]"));
+    assert_eq!(eval("reverse(\"abc\")"), error("1:14: invalid argument to `reverse`: expected a list for argument 1, found string"));
+
+    assert_eq!(eval("{ let xs = [1, 2, 3]; [pop(xs), xs] }"), eval("[3, [1, 2]]"));
+    assert_eq!(eval("pop([])"), error("1:14: invalid argument to `pop`: the list is empty"));
+    assert_eq!(eval("pop({})"), error("1:14: invalid argument to `pop`: expected a list for argument 1, found map"));
+}
+
+#[test]
+fn contains_in_lists_and_maps() {
+    assert_eq!(eval("contains([1, \"a\", [2]], [2])"), boolean(true));
+    assert_eq!(eval("contains([1, 2], 2.0)"), boolean(true));
+    assert_eq!(eval("contains([1, 2], \"1\")"), boolean(false));
+    assert_eq!(eval("contains([], 1)"), boolean(false));
+    assert_eq!(eval("contains({\"a\": 1}, \"a\")"), boolean(true));
+    assert_eq!(eval("contains({\"a\": 1}, \"b\")"), boolean(false));
+    assert_eq!(eval("contains({\"a\": 1}, 1)"), error("1:14: invalid argument to `contains`: expected a string for argument 2, found integer"));
+    assert_eq!(eval("contains(1, 1)"), error("1:14: invalid argument to `contains`: expected a string, list or map, found integer"));
+}
+
+#[test]
+fn insert_and_remove() {
+    assert_eq!(eval("{ let xs = [1, 3]; insert(xs, 1, 2); insert(xs, 3, 4); insert(xs, 0, 0); xs }"), eval("[0, 1, 2, 3, 4]"));
+    assert_eq!(eval("{ let xs = [1, 2]; insert(xs, -1, 9); xs }"), eval("[1, 9, 2]"));
+    assert_eq!(eval("insert([1], 2, 0)"), error("1:14: index 2 is out of bounds for length 1"));
+    assert_eq!(eval("insert([1], \"0\", 0)"), error("1:14: invalid argument to `insert`: expected an integer for argument 2, found string"));
+    assert_eq!(eval("{ let m = {\"a\": 1}; insert(m, \"b\", 2); insert(m, \"a\", 3); m }"), eval("{\"a\": 3, \"b\": 2}"));
+    assert_eq!(eval("insert({}, 1, 2)"), error("1:14: invalid argument to `insert`: expected a string for argument 2, found integer"));
+
+    assert_eq!(eval("{ let xs = [1, 2, 3]; [remove(xs, 0), remove(xs, -1), xs] }"), eval("[1, 3, [2]]"));
+    assert_eq!(eval("remove([1], 1)"), error("1:14: index 1 is out of bounds for length 1"));
+    assert_eq!(eval("remove([], 0)"), error("1:14: index 0 is out of bounds for length 0"));
+    assert_eq!(eval("{ let m = {\"a\": 1, \"b\": 2}; [remove(m, \"a\"), m] }"), eval("[1, {\"b\": 2}]"));
+    assert_eq!(eval("remove({}, \"a\")"), error("1:14: key \"a\" is not in the map"));
+    assert_eq!(eval("remove(\"abc\", 0)"), error("1:14: invalid argument to `remove`: expected a list for argument 1, found string"));
+}
+
+#[test]
+fn keys_values_and_entries_keep_insertion_order() {
+    let map = "{ let m = {\"b\": 1, \"a\": [2]}; m.c = 3; m.b = 4; m }";
+    assert_eq!(eval(&format!("keys({})", map)), eval("[\"b\", \"a\", \"c\"]"));
+    assert_eq!(eval(&format!("values({})", map)), eval("[4, [2], 3]"));
+    assert_eq!(eval(&format!("entries({})", map)), eval("[[\"b\", 4], [\"a\", [2]], [\"c\", 3]]"));
+    assert_eq!(eval("keys({})"), eval("[]"));
+    assert_eq!(eval("{ let m = {\"a\": 1, \"b\": 2}; remove(m, \"a\"); m.a = 3; keys(m) }"), eval("[\"b\", \"a\"]"));
+    assert_eq!(eval("values([1])"), error("1:14: invalid argument to `values`: expected a map for argument 1, found list"));
+}
+
+#[test]
+fn map_loops_follow_insertion_order() {
+    let loop_keys = |body: &str| eval(&format!(
+        "{{ let m = {{\"c\": 1, \"a\": 2, \"b\": 3}}; let ks = []; for k in m {{ {} push(ks, k); }} ks }}",
+        body
+    ));
+    assert_eq!(loop_keys(""), eval("[\"c\", \"a\", \"b\"]"));
+    // Keys removed by the body are skipped, and keys it adds are not visited.
+    assert_eq!(loop_keys("if k == \"c\" { remove(m, \"a\"); m.d = 4; }"), eval("[\"c\", \"b\"]"));
+    // A key removed and put back is visited once, in the order the loop started with.
+    assert_eq!(loop_keys("if k == \"c\" { remove(m, \"a\"); m.a = 5; }"), eval("[\"c\", \"a\", \"b\"]"));
+}
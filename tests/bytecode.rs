@@ -0,0 +1,155 @@
+mod common;
+
+use std::rc::Rc;
+use scriptx::compiler::binary::{MAGIC, VERSION};
+use scriptx::compiler::bytecode::{Bytecode, Instruction};
+use scriptx::compiler::compiler::compile;
+use scriptx::compiler::error::LoadError;
+use scriptx::datatypes::datatypes::DataType;
+use scriptx::vm::vm::Vm;
+use common::program;
+
+const SCRIPT: &str = "
+    let mut total = 0.5;
+    let names = {\"a\": 1, \"b\": 2};
+    let counter = fn() { let mut n = 0; fn() { n += 1; n } };
+    let c = counter();
+    outer: for i in 0..4 {
+        for k in names { if i == 3 { break outer; } total += names[k] * i; }
+        c();
+    }
+    let done = c() > 2 && !false;
+    let word = \"hé\"[0..1];
+";
+
+fn compiled(source: &str) -> Bytecode {
+    compile(&program(source))
+}
+
+fn run(bytecode: Bytecode) -> String {
+    let mut vm = Vm::new();
+    let error = vm.run(&Rc::new(bytecode)).err().map(|e| e.to_string());
+    format!("{:?} {:?}", vm.bindings(), error)
+}
+
+/// Rewrites the checksum after a deliberate change to the contents, so the
+/// change reaches the checks behind it.
+fn reseal(bytes: &mut [u8]) {
+    let checksum = bytes[14..].iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    bytes[6..14].copy_from_slice(&checksum.to_le_bytes());
+}
+
+#[test]
+fn round_trip() {
+    let bytecode = compiled(SCRIPT);
+    let bytes = bytecode.to_bytes();
+    assert!(bytes.starts_with(MAGIC));
+    assert_eq!(Bytecode::from_bytes(&bytes), Ok(bytecode.clone()));
+    assert_eq!(run(Bytecode::from_bytes(&bytes).unwrap()), run(bytecode));
+}
+
+#[test]
+fn runtime_errors_keep_their_spans() {
+    let bytecode = compiled("let xs = [1];\nlet y = xs[3];");
+    let reloaded = Bytecode::from_bytes(&bytecode.to_bytes()).unwrap();
+    assert_eq!(run(reloaded), run(bytecode));
+}
+
+#[test]
+fn rejects_other_files() {
+    assert_eq!(Bytecode::from_bytes(b"let a = 1;"), Err(LoadError::NotBytecode));
+    assert_eq!(Bytecode::from_bytes(b""), Err(LoadError::NotBytecode));
+
+    let mut bytes = compiled(SCRIPT).to_bytes();
+    bytes[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+    assert_eq!(Bytecode::from_bytes(&bytes), Err(LoadError::UnsupportedVersion { found: VERSION + 1, supported: VERSION }));
+}
+
+#[test]
+fn rejects_corruption() {
+    let bytes = compiled(SCRIPT).to_bytes();
+
+    let mut flipped = bytes.clone();
+    let middle = flipped.len() / 2;
+    flipped[middle] ^= 0x10;
+    assert_eq!(Bytecode::from_bytes(&flipped), Err(LoadError::ChecksumMismatch));
+
+    assert_eq!(Bytecode::from_bytes(&bytes[..10]), Err(LoadError::Truncated));
+
+    let mut truncated = bytes[..bytes.len() - 3].to_vec();
+    reseal(&mut truncated);
+    assert_eq!(Bytecode::from_bytes(&truncated), Err(LoadError::Truncated));
+
+    let mut extended = bytes.clone();
+    extended.push(0);
+    reseal(&mut extended);
+    assert!(matches!(Bytecode::from_bytes(&extended), Err(LoadError::Malformed(_))));
+}
+
+#[test]
+fn rejects_unsafe_code() {
+    let mut bytecode = compiled("let a = 1;");
+    let end = bytecode.functions[0].code.len() as u32;
+    bytecode.functions[0].code[0] = Instruction::Jump(end + 5);
+    assert!(matches!(
+        Bytecode::from_bytes(&bytecode.to_bytes()),
+        Err(LoadError::Invalid { function: 0, instruction: Some(0), .. })
+    ));
+
+    let mut bytecode = compiled("let a = 1;");
+    bytecode.functions[0].code.insert(0, Instruction::Pop);
+    let span = bytecode.functions[0].spans[0];
+    bytecode.functions[0].spans.insert(0, span);
+    assert!(matches!(Bytecode::from_bytes(&bytecode.to_bytes()), Err(LoadError::Invalid { .. })));
+
+    let mut bytecode = compiled("let f = fn() { 1 }; let r = f();");
+    bytecode.functions[0].code.retain(|instruction| *instruction != Instruction::Return);
+    bytecode.functions[0].spans.pop();
+    assert!(matches!(Bytecode::from_bytes(&bytecode.to_bytes()), Err(LoadError::Invalid { .. })));
+
+    let mut bytecode = compiled("let a = 1;");
+    bytecode.functions[0].code[0] = Instruction::GetLocal(bytecode.functions[0].slots);
+    assert!(matches!(Bytecode::from_bytes(&bytecode.to_bytes()), Err(LoadError::Invalid { .. })));
+
+    // Operands big enough to overflow the stack depth arithmetic.
+    for instruction in [Instruction::Pick(u32::MAX), Instruction::Call(u32::MAX)] {
+        let mut bytecode = compiled("let a = 1;");
+        bytecode.functions[0].code[0] = instruction;
+        assert!(
+            matches!(Bytecode::from_bytes(&bytecode.to_bytes()), Err(LoadError::Invalid { function: 0, instruction: Some(0), .. })),
+            "{:?} is rejected", instruction
+        );
+    }
+}
+
+#[test]
+fn rejects_oversized_functions() {
+    // The vm allocates every slot up front, so these would abort it.
+    let mut bytecode = compiled("let f = fn(a) { a }; let r = f(1);");
+    bytecode.functions[1].slots = u32::MAX;
+    assert!(matches!(
+        Bytecode::from_bytes(&bytecode.to_bytes()),
+        Err(LoadError::Invalid { function: 1, instruction: None, .. })
+    ));
+
+    let mut bytecode = compiled("for i in 0..3 { }");
+    bytecode.functions[0].iterators = u32::MAX;
+    assert!(matches!(
+        Bytecode::from_bytes(&bytecode.to_bytes()),
+        Err(LoadError::Invalid { function: 0, instruction: None, .. })
+    ));
+}
+
+#[test]
+fn rejects_map_keys_that_are_not_strings() {
+    let mut bytecode = compiled("let m = {\"a\": 1};");
+    let number = bytecode.constants.iter().position(|constant| matches!(constant, DataType::Number(_))).unwrap() as u32;
+    let at = bytecode.functions[0].code.iter().position(|instruction| matches!(instruction, Instruction::MapEntry(_))).unwrap();
+    bytecode.functions[0].code[at] = Instruction::MapEntry(number);
+    assert_eq!(
+        Bytecode::from_bytes(&bytecode.to_bytes()),
+        Err(LoadError::Invalid { function: 0, instruction: Some(at), reason: String::from("a name is not a string constant") })
+    );
+}
@@ -0,0 +1,66 @@
+// Shared by the integration tests, each of which uses only some of these.
+#![allow(dead_code)]
+
+use std::rc::Rc;
+use scriptx::compiler::compiler::compile;
+use scriptx::datatypes::datatypes::{DataType, NumberType};
+use scriptx::datatypes::function::STACK_SIZE;
+use scriptx::interpreter::interpreter::Interpreter;
+use scriptx::lexer::lexer::tokenize;
+use scriptx::parser::parser::{parse, Parser, Program};
+use scriptx::vm::vm::Vm;
+
+pub fn program(source: &str) -> Program {
+    let tokens = tokenize(source).expect("lex failed");
+    parse(&mut Parser::new(tokens)).expect("parse failed")
+}
+
+/// Every parse error in `source`, as its message.
+pub fn parse_errors(source: &str) -> Vec<String> {
+    let tokens = tokenize(source).expect("lex failed");
+    parse(&mut Parser::new(tokens)).err().unwrap_or_default().iter().map(|e| e.to_string()).collect()
+}
+
+/// The value of `result`, or the error the run stopped with. Both engines
+/// have to agree on it.
+pub fn eval(expression: &str) -> Result<DataType, String> {
+    let program = program(&format!("let result = {};", expression));
+
+    let mut interpreter = Interpreter::new();
+    let walked = interpreter.run(&program).map(|_| interpreter.get("result").unwrap()).map_err(|e| e.to_string());
+
+    let mut vm = Vm::new();
+    let executed = vm.run(&Rc::new(compile(&program))).map(|_| vm.get("result").unwrap()).map_err(|e| e.to_string());
+
+    assert_eq!(walked, executed, "both engines agree on {}", expression);
+    walked
+}
+
+/// Runs `test` on a thread with the stack the interpreter needs for deep
+/// recursion, passing on its panic if it fails.
+pub fn on_script_stack(test: impl FnOnce() + Send + 'static) {
+    let thread = std::thread::Builder::new().stack_size(STACK_SIZE).spawn(test).expect("thread spawned");
+    if let Err(panic) = thread.join() {
+        std::panic::resume_unwind(panic);
+    }
+}
+
+pub fn int(v: i64) -> Result<DataType, String> {
+    Ok(DataType::Number(NumberType::Integer(v)))
+}
+
+pub fn float(v: f64) -> Result<DataType, String> {
+    Ok(DataType::Number(NumberType::Float(v)))
+}
+
+pub fn string(s: &str) -> Result<DataType, String> {
+    Ok(DataType::String(Rc::from(s)))
+}
+
+pub fn boolean(b: bool) -> Result<DataType, String> {
+    Ok(DataType::Boolean(b))
+}
+
+pub fn error(message: &str) -> Result<DataType, String> {
+    Err(String::from(message))
+}
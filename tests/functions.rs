@@ -0,0 +1,45 @@
+mod common;
+
+use scriptx::datatypes::function::MAX_CALL_DEPTH;
+use common::{error, eval, int, on_script_stack};
+
+#[test]
+fn closures_capture_their_environment() {
+    assert_eq!(eval("{ let add = fn(n) { fn(x) { x + n } }; let add2 = add(2); add2(3) }"), int(5));
+    // Captured variables are shared, not copied.
+    assert_eq!(eval("{ let mut count = 0; let bump = fn() { count += 1; count }; bump(); bump(); count }"), int(2));
+    assert_eq!(
+        eval("{ let counter = fn() { let mut n = 0; fn() { n += 1; n } }; let a = counter(); let b = counter(); a(); a(); b(); a() }"),
+        int(3)
+    );
+    // Each iteration gets a binding of its own.
+    assert_eq!(eval("{ let fs = []; for i in 0..3 { push(fs, fn() { i * 10 }); } fs[0]() + fs[2]() }"), int(20));
+    assert_eq!(eval("{ let fact = fn(n) { if n < 2 { 1 } else { n * fact(n - 1) } }; fact(10) }"), int(3628800));
+}
+
+#[test]
+fn return_leaves_the_function() {
+    assert_eq!(eval("{ let f = fn(x) { if x > 0 { return 1; } 2 }; [f(1), f(-1)] }"), eval("[1, 2]"));
+    assert_eq!(eval("{ let f = fn() { for i in 0..10 { if i == 3 { return i; } } }; f() }"), int(3));
+}
+
+#[test]
+fn calls_check_the_argument_count() {
+    assert_eq!(eval("{ let f = fn(a, b) { a }; f(1) }"), error("1:40: `f` takes 2 argument(s) but 1 were given"));
+    assert_eq!(eval("{ let f = fn() { 1 }; f(1, 2) }"), error("1:36: `f` takes 0 argument(s) but 2 were given"));
+    assert_eq!(eval("(fn(a) { a })()"), error("1:15: `<anonymous fn>` takes 1 argument(s) but 0 were given"));
+    assert_eq!(eval("len()"), error("1:14: `len` takes 1 argument(s) but 0 were given"));
+    assert_eq!(eval("1(2)"), error("1:14: a value of type integer is not callable"));
+}
+
+#[test]
+fn recursion_stops_at_the_call_depth_limit() {
+    on_script_stack(|| {
+        let depth = |n: usize| eval(&format!("{{ let f = fn(n) {{ if n == 0 {{ 0 }} else {{ 1 + f(n - 1) }} }}; f({}) }}", n));
+        assert_eq!(depth(MAX_CALL_DEPTH - 1), int(MAX_CALL_DEPTH as i64 - 1));
+        assert_eq!(depth(MAX_CALL_DEPTH), error(&format!("1:59: stack overflow: more than {} nested calls", MAX_CALL_DEPTH)));
+        // Every call nested as deep as the parser allows takes the most stack.
+        let nested = format!("{{ let f = fn(n) {{ if n == 0 {{ 0 }} else {{ 1 + {}f(n - 1) }} }}; f(2000) }}", "-".repeat(118));
+        assert_eq!(eval(&nested), error(&format!("1:177: stack overflow: more than {} nested calls", MAX_CALL_DEPTH)));
+    });
+}
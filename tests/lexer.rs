@@ -0,0 +1,101 @@
+use scriptx::lexer::lexer::tokenize;
+use scriptx::lexer::token::Token;
+
+/// The value of the single string literal `source` holds.
+fn string(source: &str) -> String {
+    let tokens = tokenize(source).expect("lex failed");
+    match &tokens[0].token {
+        Token::String(value) => value.to_string(),
+        other => panic!("expected a string, found {:?}", other),
+    }
+}
+
+fn lex_error(source: &str) -> String {
+    tokenize(source).expect_err("lexing succeeded").to_string()
+}
+
+#[test]
+fn string_escapes() {
+    assert_eq!(string(r#""a\nb""#), "a\nb");
+    assert_eq!(string(r#""a\tb\rc""#), "a\tb\rc");
+    assert_eq!(string(r#""say \"hi\"""#), "say \"hi\"");
+    assert_eq!(string(r#""back\\slash""#), "back\\slash");
+    assert_eq!(string(r#""nul\0""#), "nul\0");
+    assert_eq!(string(r#""\u{41}\u{e9}\u{1F600}""#), "Aé😀");
+    assert_eq!(string(r#""\u{10FFFF}""#), "\u{10FFFF}");
+}
+
+#[test]
+fn invalid_escapes() {
+    assert_eq!(lex_error(r#""\q""#), "1:1: invalid escape sequence `\\q`");
+    assert_eq!(lex_error(r#""\u41""#), "1:1: invalid escape sequence `\\u`");
+    assert_eq!(lex_error(r#""\u{}""#), "1:1: invalid escape sequence `\\u{}`");
+    assert_eq!(lex_error(r#""\u{110000}""#), "1:1: invalid escape sequence `\\u{110000}`");
+    assert_eq!(lex_error(r#""\u{D800}""#), "1:1: invalid escape sequence `\\u{D800}`");
+    assert_eq!(lex_error(r#""\u{1234567}""#), "1:1: invalid escape sequence `\\u{1234567}`");
+    // The first bad escape is reported, and the rest of the string is still read.
+    assert_eq!(lex_error(r#"let s = "\x \y"; let t = 1;"#), "1:9: invalid escape sequence `\\x`");
+}
+
+#[test]
+fn unterminated_strings() {
+    assert_eq!(lex_error(r#"let s = "abc"#), "1:9: unterminated string literal");
+    assert_eq!(lex_error(r#"let s = "abc\"#), "1:9: unterminated string literal");
+    assert_eq!(lex_error(r#"let s = "\u{41"#), "1:9: unterminated string literal");
+    assert_eq!(lex_error(r#"let s = "\u{"#), "1:9: unterminated string literal");
+    assert_eq!(lex_error(r#"let s = "\u{41 x";"#), "1:9: invalid escape sequence `\\u{41`");
+}
+
+fn tokens(source: &str) -> Vec<Token> {
+    tokenize(source).expect("lex failed").into_iter().map(|token| token.token).collect()
+}
+
+#[test]
+fn comments_are_skipped() {
+    let plain = tokens("let a = 1;");
+    assert_eq!(tokens("let a = 1; // a comment / with slashes"), plain);
+    assert_eq!(tokens("// first\nlet a = 1;\n// last"), plain);
+    assert_eq!(tokens("let a /* here */ = 1;"), plain);
+    assert_eq!(tokens("/* one /* two /* three */ */ still out */ let a = 1;"), plain);
+    assert_eq!(tokens("/* // not a line comment */ let a = 1;"), plain);
+    assert_eq!(tokens("//// four slashes\nlet a = 1;"), plain);
+    assert_eq!(tokens("let a = 4 / 2;").len(), plain.len() + 2);
+}
+
+#[test]
+fn doc_comments_are_tokens() {
+    assert_eq!(tokens("/// Says hi.\nlet a = 1;")[0], Token::DocComment("Says hi.".into()));
+    assert_eq!(tokens("///no space")[0], Token::DocComment("no space".into()));
+}
+
+#[test]
+fn unterminated_block_comments() {
+    assert_eq!(lex_error("let a = 1; /* never closed"), "1:12: unterminated block comment");
+    assert_eq!(lex_error("/* /* nested */ but the outer one is open"), "1:1: unterminated block comment");
+}
+
+#[test]
+fn unicode_identifiers_and_strings() {
+    assert_eq!(tokens("let café = 1;")[1], Token::IDENTIFIER("café".into()));
+    assert_eq!(tokens("let 名前 = 1;")[1], Token::IDENTIFIER("名前".into()));
+    assert_eq!(tokens("let ñ_2 = 1;")[1], Token::IDENTIFIER("ñ_2".into()));
+    assert_eq!(string("\"héllo, 世界 😀\""), "héllo, 世界 😀");
+}
+
+#[test]
+fn columns_count_characters_after_multi_byte_ones() {
+    let tokens = tokenize("let 名前 = \"é😀\"; x").expect("lex failed");
+    let positions: Vec<_> = tokens.iter().map(|token| (token.span.start, token.span.end, token.span.column)).collect();
+    // Spans are byte offsets; columns count characters.
+    assert_eq!(positions, vec![(0, 3, 1), (4, 10, 5), (11, 12, 8), (13, 21, 10), (21, 22, 14), (23, 24, 16), (24, 24, 17)]);
+
+    assert_eq!(lex_error("let 名前 = 1 @ 2;"), "1:12: unexpected character `@`");
+    assert_eq!(lex_error("\"é\"\n  éü #"), "2:6: unexpected character `#`");
+    assert_eq!(lex_error("let s = \"😀\\q\";"), "1:9: invalid escape sequence `\\q`");
+}
+
+#[test]
+fn a_nul_byte_is_not_the_end_of_the_input() {
+    assert_eq!(lex_error("let a = 1;\0 let b = 2;"), "1:11: unexpected character `\\0`");
+    assert_eq!(string("\"a\0b\""), "a\0b");
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use common::{error, eval, int};
+
+#[test]
+fn break_and_continue_leave_the_innermost_loop() {
+    assert_eq!(eval("{ let xs = []; for i in 0..10 { if i == 4 { break; } if i % 2 == 1 { continue; } push(xs, i); } xs }"), eval("[0, 2]"));
+    assert_eq!(eval("{ let mut i = 0; while true { i += 1; if i == 5 { break; } } i }"), int(5));
+    assert_eq!(
+        eval("{ let pairs = []; for i in 0..3 { for j in 0..3 { if j > i { break; } push(pairs, [i, j]); } } pairs }"),
+        eval("[[0, 0], [1, 0], [1, 1], [2, 0], [2, 1], [2, 2]]")
+    );
+}
+
+#[test]
+fn labels_reach_outer_loops() {
+    assert_eq!(
+        eval("{ let pairs = []; outer: for i in 0..3 { for j in 0..3 { if j == 1 { continue outer; } push(pairs, [i, j]); } } pairs }"),
+        eval("[[0, 0], [1, 0], [2, 0]]")
+    );
+    assert_eq!(
+        eval("{ let pairs = []; outer: for i in 0..3 { let mut j = 0; while true { if i == 1 && j == 1 { break outer; } push(pairs, [i, j]); j += 1; if j == 2 { break; } } } pairs }"),
+        eval("[[0, 0], [0, 1], [1, 0]]")
+    );
+    // Three deep, skipping the middle loop's label.
+    assert_eq!(
+        eval("{ let mut n = 0; a: for i in 0..3 { b: for j in 0..3 { for k in 0..3 { if k == 1 { continue a; } n += 1; } } } n }"),
+        int(3)
+    );
+}
+
+#[test]
+fn loop_control_outside_a_loop_is_an_error() {
+    assert_eq!(eval("{ break; 1 }"), error("1:16: `break` outside of a loop"));
+    assert_eq!(eval("{ let f = fn() { continue; }; for i in 0..2 { f(); } }"), error("1:31: `continue` outside of a loop"));
+    assert_eq!(eval("{ inner: for i in 0..2 { } for i in 0..2 { break inner; } }"), error("1:57: `break inner` is not inside a loop labeled `inner`"));
+}
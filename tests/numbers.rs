@@ -0,0 +1,49 @@
+mod common;
+
+use scriptx::datatypes::datatypes::DataType;
+use common::{eval, float, int, parse_errors};
+
+#[test]
+fn integers_are_64_bit() {
+    assert_eq!(eval("3000000000 * 3"), int(9_000_000_000));
+    assert_eq!(eval("9223372036854775807"), int(i64::MAX));
+    assert_eq!(eval("-9223372036854775807 - 1"), int(i64::MIN));
+    assert_eq!(eval("[1, 9007199254740993, 9007199254740992][1..]"), eval("[9007199254740993, 9007199254740992]"));
+    assert_eq!(
+        eval("{ let xs = [9007199254740993, 9007199254740992]; sort(xs); xs[0] }"),
+        int(9_007_199_254_740_992)
+    );
+}
+
+#[test]
+fn floats_are_64_bit() {
+    assert_eq!(eval("0.1 + 0.2"), float(0.1 + 0.2));
+    assert_eq!(eval("19.99 * 3"), float(19.99 * 3.0));
+    assert_eq!(eval("86400.5 * 365 + 1"), float(86400.5 * 365.0 + 1.0));
+    assert_eq!(eval("16777217 + 0.0 == 16777217"), Ok(DataType::Boolean(true)));
+}
+
+#[test]
+fn integer_overflow_is_an_error() {
+    assert_eq!(eval("9223372036854775807 + 1"), Err(String::from("1:14: integer overflow in addition")));
+    assert_eq!(eval("-9223372036854775807 - 2"), Err(String::from("1:14: integer overflow in subtraction")));
+    assert_eq!(eval("4611686018427387904 * 2"), Err(String::from("1:14: integer overflow in multiplication")));
+    assert_eq!(eval("(-9223372036854775807 - 1) / -1"), Err(String::from("1:15: integer overflow in division")));
+    assert_eq!(eval("-(-9223372036854775807 - 1)"), Err(String::from("1:14: integer overflow in negation")));
+    // Mixing in a float works in floats instead.
+    assert_eq!(eval("9223372036854775807 + 1.0"), float(9_223_372_036_854_775_807.0 + 1.0));
+}
+
+#[test]
+fn out_of_range_literals_are_parse_errors() {
+    assert_eq!(
+        parse_errors("let a = 1;\nlet b = 9223372036854775808;"),
+        vec!["2:9: expected an integer no greater than 9223372036854775807, found number `9223372036854775808`"]
+    );
+    let huge = format!("let a = 1{}.5;", "0".repeat(400));
+    assert_eq!(
+        parse_errors(&huge),
+        vec![format!("1:9: expected a float within the range of 64-bit floats, found number `1{}.5`", "0".repeat(400))]
+    );
+    assert!(parse_errors(&format!("let a = 1{}.0;", "0".repeat(308))).is_empty());
+}
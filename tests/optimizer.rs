@@ -0,0 +1,116 @@
+mod common;
+
+use std::rc::Rc;
+use scriptx::compiler::compiler::compile;
+use scriptx::interpreter::interpreter::Interpreter;
+use scriptx::optimizer::optimizer::optimize;
+use scriptx::parser::expression::Expr;
+use scriptx::parser::parser::Program;
+use scriptx::parser::visitor::{walk_expr, Visitor};
+use scriptx::vm::vm::Vm;
+use common::program;
+
+fn optimized(source: &str) -> Program {
+    let mut program = program(source);
+    optimize(&mut program);
+    program
+}
+
+/// What is left of a program: the kind of every expression in it, with
+/// literals shown by value.
+#[derive(Default)]
+struct Shape(Vec<String>);
+
+impl Visitor for Shape {
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.0.push(match expr {
+            Expr::Literal(literal) => format!("{:?}", literal.value),
+            Expr::Identifier(identifier) => identifier.name.to_string(),
+            other => format!("{:?}", other).split('(').next().unwrap().to_string(),
+        });
+        walk_expr(self, expr);
+    }
+}
+
+fn shape(source: &str) -> Vec<String> {
+    let mut shape = Shape::default();
+    shape.visit_program(&optimized(source));
+    shape.0
+}
+
+fn run(program: &Program) -> (String, String) {
+    let mut interpreter = Interpreter::new();
+    let error = interpreter.run(program).err().map(|e| e.to_string());
+    let walked = format!("{:?} {:?}", interpreter.bindings(), error);
+
+    let mut vm = Vm::new();
+    let error = vm.run(&Rc::new(compile(program))).err().map(|e| e.to_string());
+    let bindings: Vec<_> = vm.bindings().into_iter().filter(|(_, value)| value.type_name() != "function").collect();
+    (walked, format!("{:?} {:?}", bindings, error))
+}
+
+/// The optimized program does just what the original did, on both engines.
+fn assert_unchanged(source: &str) {
+    assert_eq!(run(&optimized(source)), run(&program(source)), "for:\n{}", source);
+}
+
+#[test]
+fn folds_constant_operators() {
+    assert_eq!(shape("let foo = 5 * (10 + 2);"), vec!["Number(Integer(60))"]);
+    assert_eq!(shape("let a = 1.5 * 2 < 4 == !false;"), vec!["Boolean(true)"]);
+    assert_eq!(shape("let s = \"ab\" + \"cd\";"), vec!["String(\"abcd\")"]);
+    assert_eq!(shape("let a = -(3 - 5);"), vec!["Number(Integer(2))"]);
+    assert_eq!(shape("let a = x + (1 + 1);"), vec!["Binary", "x", "Number(Integer(2))"]);
+}
+
+#[test]
+fn folds_short_circuiting_operators() {
+    assert_eq!(shape("let a = true || f();"), vec!["Boolean(true)"]);
+    assert_eq!(shape("let a = 1 > 2 && f();"), vec!["Boolean(false)"]);
+    assert_eq!(shape("let a = false || 2 > 1;"), vec!["Boolean(true)"]);
+    assert_eq!(shape("let a = true && f();"), vec!["Binary", "Boolean(true)", "Call", "f"]);
+}
+
+#[test]
+fn keeps_operations_that_fail() {
+    assert_eq!(shape("let a = 1 / 0;"), vec!["Binary", "Number(Integer(1))", "Number(Integer(0))"]);
+    assert_eq!(shape("let a = 9223372036854775807 + 1;"), vec!["Binary", "Number(Integer(9223372036854775807))", "Number(Integer(1))"]);
+    assert_eq!(shape("let a = -\"x\";"), vec!["Unary", "String(\"x\")"]);
+    assert_eq!(shape("let a = false || 1;"), vec!["Binary", "Boolean(false)", "Number(Integer(1))"]);
+    for source in [
+        "let a = 1 / 0;",
+        "let b = 1; let a = 10 % (b - 1);",
+        "let a = 9223372036854775807 + 1;",
+        "let a = \"a\" * (1 + 1);",
+        "let a = -\"x\";",
+        "let a = !(1 + 1);",
+        "let a = false || 1;",
+        "let a = 1 && true;",
+        "let a = if 1 + 1 { 2 };",
+        "for i in 0..(1 < 2) { }",
+    ] {
+        assert_unchanged(source);
+    }
+}
+
+#[test]
+fn removes_branches_that_never_run() {
+    assert_eq!(shape("let a = if 1 > 2 { f() } else { 3 };"), vec!["Block", "Number(Integer(3))"]);
+    assert_eq!(shape("let a = if true { 1 } else { f() };"), vec!["Block", "Number(Integer(1))"]);
+    assert_eq!(shape("let a = if false { f() };"), vec!["Block"]);
+    assert_eq!(shape("let a = if false { f() } else if x { 2 };"), vec!["If", "x", "Number(Integer(2))"]);
+    assert_unchanged("let x = 1; let a = if x == 1 && 2 > 1 { let x = 5; x } else { 0 }; let b = if !true { 1 };");
+}
+
+#[test]
+fn removes_code_after_return() {
+    assert_eq!(shape("let f = fn() { return 1; g(); 2 };"), vec!["Function", "Number(Integer(1))"]);
+    assert_eq!(shape("while true { break; g(); }"), vec!["Boolean(true)"]);
+    assert_unchanged("
+        let mut seen = [];
+        let f = fn(n) { if n > 2 { return n; push(seen, n); } push(seen, n); n * 10 };
+        let a = f(1);
+        let b = f(3);
+        for i in 0..5 { if i == 1 { continue; push(seen, i); } if i == 3 { break; 1 / 0; } push(seen, -i); }
+    ");
+}
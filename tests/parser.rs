@@ -0,0 +1,110 @@
+mod common;
+
+use scriptx::lexer::lexer::tokenize;
+use scriptx::parser::error::{Expected, ParseErrorKind};
+use scriptx::parser::parser::{parse, Parser, Program, MAX_NESTING};
+use common::parse_errors;
+
+fn parse_source(source: &str) -> Result<Program, Vec<String>> {
+    let tokens = tokenize(source).expect("lex failed");
+    parse(&mut Parser::new(tokens)).map_err(|errors| errors.iter().map(|e| e.to_string()).collect())
+}
+
+#[test]
+fn deep_nesting_is_an_error() {
+    let parentheses = format!("let r = {}1{};", "(".repeat(5000), ")".repeat(5000));
+    assert_eq!(parse_errors(&parentheses), vec![format!("1:{}: expression nested too deeply", 9 + MAX_NESTING)]);
+
+    let negations = format!("let r = {}1;", "-".repeat(20000));
+    assert_eq!(parse_errors(&negations), vec![format!("1:{}: expression nested too deeply", 9 + MAX_NESTING)]);
+
+    let blocks = format!("let r = {}{};", "{ ".repeat(5000), "}".repeat(5000));
+    // A block and the expression in it are a level each.
+    assert_eq!(parse_errors(&blocks), vec![format!("1:{}: expression nested too deeply", 9 + MAX_NESTING)]);
+}
+
+#[test]
+fn nesting_up_to_the_limit_parses() {
+    let depth = MAX_NESTING - 1;
+    assert!(parse_source(&format!("let r = {}1{};", "(".repeat(depth), ")".repeat(depth))).is_ok());
+    assert!(parse_source(&format!("let r = {}1;", "-".repeat(depth))).is_ok());
+    let blocks = format!("let r = {}{};", "{ ".repeat(depth / 2), "}".repeat(depth / 2));
+    assert!(parse_source(&blocks).is_ok());
+}
+
+#[test]
+fn every_independent_error_is_reported() {
+    assert_eq!(
+        parse_errors("let a = ;\nlet b = 2;\nlet c = 1 +;\nlet d = 4;"),
+        vec!["1:9: expected an expression, found `;`", "3:12: expected an expression, found `;`"]
+    );
+    assert_eq!(
+        parse_errors("let = 1; let ok = 2; while ) { }"),
+        vec!["1:5: expected an identifier, found `=`", "1:28: expected an expression, found `)`"]
+    );
+}
+
+#[test]
+fn an_error_inside_a_block_skips_the_rest_of_it() {
+    assert_eq!(parse_errors("let f = fn() { let x = ; x };"), vec!["1:24: expected an expression, found `;`"]);
+    assert_eq!(
+        parse_errors("let f = fn() { if true { let = 1; } 2 };\nlet g = fn() { 1 +; };"),
+        vec!["1:30: expected an identifier, found `=`", "2:19: expected an expression, found `;`"]
+    );
+    assert_eq!(
+        parse_errors("let a = 1; } let b = ;"),
+        vec!["1:12: expected a statement, found `}`", "1:22: expected an expression, found `;`"]
+    );
+}
+
+#[test]
+fn doc_comments_attach_to_the_let_after_them() {
+    let program = parse_source("/// One\n/// line each.\nlet a = 1;\nlet b = 2;\n{\n/// Inner.\nlet c = 3; c; }").unwrap();
+    let docs: Vec<_> = program.statements().iter().map(|statement| statement.doc().map(|doc| doc.to_string())).collect();
+    assert_eq!(docs, vec![Some(String::from("One\nline each.")), None, None]);
+}
+
+#[test]
+fn doc_comments_elsewhere_are_errors() {
+    assert_eq!(
+        parse_errors("/// Loops.\nfor i in 0..3 { }"),
+        vec!["1:1: doc comment before `for` instead of a `let`"]
+    );
+    assert_eq!(
+        parse_errors("let a = /// The value.\n 1;\nlet b = 2; /// Trailing."),
+        vec!["1:9: doc comment before number `1` instead of a `let`", "3:12: doc comment before end of file instead of a `let`"]
+    );
+    assert_eq!(
+        parse_errors("let f = fn() {\n/// Nothing here.\n};"),
+        vec!["2:1: doc comment before `}` instead of a `let`"]
+    );
+}
+
+#[test]
+fn unknown_statement_starts() {
+    assert_eq!(parse_errors("let a = 1;\n  ) a;"), vec!["2:3: expected a statement, found `)`"]);
+    assert_eq!(parse_errors("let f = fn() { 1; =; };"), vec!["1:19: expected a statement or `}`, found `=`"]);
+
+    let tokens = tokenize("let a = 1; in").expect("lex failed");
+    let error = &parse(&mut Parser::new(tokens)).err().unwrap()[0];
+    assert_eq!((error.span.start, error.span.end, error.span.line, error.span.column), (11, 13, 1, 12));
+}
+
+#[test]
+fn errors_inside_expressions_do_not_add_up_to_deep_nesting() {
+    let source = "let a = (1;\n".repeat(MAX_NESTING + 72);
+    let found = parse_errors(&source);
+    assert_eq!(found.len(), MAX_NESTING + 72);
+    assert_eq!(found.last().unwrap(), &format!("{}:11: expected `)`, found `;`", MAX_NESTING + 72));
+}
+
+#[test]
+fn errors_say_what_kind_they_are() {
+    let kinds = |source: &str| -> Vec<ParseErrorKind> {
+        let tokens = tokenize(source).expect("lex failed");
+        parse(&mut Parser::new(tokens)).err().unwrap().into_iter().map(|error| error.kind).collect()
+    };
+    assert_eq!(kinds(&format!("let r = {}1;", "(".repeat(MAX_NESTING + 1))), vec![ParseErrorKind::TooDeep]);
+    assert_eq!(kinds("/// Loops.\nfor i in 0..3 { }"), vec![ParseErrorKind::DanglingDoc]);
+    assert_eq!(kinds("let = 1;"), vec![ParseErrorKind::Unexpected { expected: vec![Expected::Identifier] }]);
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::{boolean, eval, int};
+
+#[test]
+fn subtraction_is_left_associative() {
+    assert_eq!(eval("10 - 2 - 3"), int(5));
+}
+
+#[test]
+fn division_is_left_associative() {
+    assert_eq!(eval("100 / 10 / 5"), int(2));
+}
+
+#[test]
+fn multiplication_binds_tighter_than_addition() {
+    assert_eq!(eval("2 * 3 + 4"), int(10));
+    assert_eq!(eval("2 + 3 * 4"), int(14));
+    assert_eq!(eval("10 - 6 / 2"), int(7));
+}
+
+#[test]
+fn parentheses_override_precedence() {
+    assert_eq!(eval("(2 + 3) * 4"), int(20));
+    assert_eq!(eval("10 - (2 - 3)"), int(11));
+    assert_eq!(eval("5 * (10 + 2)"), int(60));
+}
+
+#[test]
+fn mixed_chain() {
+    assert_eq!(eval("1 + 2 * 3 - 4 / 2 + 5"), int(10));
+}
+
+#[test]
+fn comparison_binds_looser_than_arithmetic() {
+    assert_eq!(eval("1 + 2 < 2 * 2"), boolean(true));
+    assert_eq!(eval("10 - 4 >= 3 * 2"), boolean(true));
+    assert_eq!(eval("2 * 3 > 3 + 3"), boolean(false));
+    assert_eq!(eval("4 <= 2 + 1"), boolean(false));
+}
+
+#[test]
+fn equality_binds_looser_than_comparison() {
+    assert_eq!(eval("1 < 2 == 2 < 3"), boolean(true));
+    assert_eq!(eval("1 > 2 != 2 > 3"), boolean(false));
+    assert_eq!(eval("2 + 2 == 4"), boolean(true));
+}
+
+#[test]
+fn numbers_compare_across_integer_and_float() {
+    assert_eq!(eval("1 == 1.0"), boolean(true));
+    assert_eq!(eval("2 > 1.5"), boolean(true));
+    assert_eq!(eval("2.5 <= 2"), boolean(false));
+}
+
+#[test]
+fn equality_is_structural() {
+    assert_eq!(eval("\"abc\" == \"ab\" + \"c\""), boolean(true));
+    assert_eq!(eval("\"abc\" < \"abd\""), boolean(true));
+    assert_eq!(eval("true == true"), boolean(true));
+    assert_eq!(eval("true != 1"), boolean(true));
+    assert_eq!(eval("split(\"a,b\", \",\") == split(\"a;b\", \";\")"), boolean(true));
+}
+
+#[test]
+fn prefix_operators_bind_tighter_than_infix() {
+    assert_eq!(eval("-2 * 3"), int(-6));
+    assert_eq!(eval("-(2 + 3)"), int(-5));
+    assert_eq!(eval("2 - -3"), int(5));
+    assert_eq!(eval("+4 - 1"), int(3));
+    assert_eq!(eval("!true == false"), boolean(true));
+    assert_eq!(eval("!!true"), boolean(true));
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    assert_eq!(eval("true || false && false"), boolean(true));
+    assert_eq!(eval("(true || false) && false"), boolean(false));
+    assert_eq!(eval("1 < 2 && 2 < 3"), boolean(true));
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    // The right operands would fail with a division by zero if evaluated.
+    assert_eq!(eval("false && 1 / 0 == 1"), boolean(false));
+    assert_eq!(eval("true || 1 / 0 == 1"), boolean(true));
+}
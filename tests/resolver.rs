@@ -0,0 +1,164 @@
+mod common;
+
+use scriptx::interpreter::interpreter::Interpreter;
+use scriptx::parser::identifier::Identifier;
+use scriptx::parser::parser::Program;
+use scriptx::parser::visitor::Visitor;
+use scriptx::resolver::diagnostic::DiagnosticKind;
+use scriptx::resolver::resolver::resolve;
+use common::{error, eval, int, program};
+
+/// Each diagnostic as its message and the line and column it points at.
+fn diagnostics(source: &str) -> Vec<String> {
+    resolve(&mut program(source)).iter().map(|diagnostic| diagnostic.to_string()).collect()
+}
+
+fn run(program: &Program) -> String {
+    let mut interpreter = Interpreter::new();
+    let error = interpreter.run(program).err().map(|e| e.to_string());
+    format!("{:?} {:?}", interpreter.bindings(), error)
+}
+
+/// Running a program that was resolved already gives the same result as
+/// leaving that to the interpreter.
+fn assert_resolved_runs_the_same(source: &str) {
+    let unresolved = program(source);
+    let mut resolved = unresolved.clone();
+    let errors: Vec<_> = resolve(&mut resolved).into_iter().filter(|d| d.is_error()).collect();
+    assert!(errors.is_empty(), "{:?} for:\n{}", errors, source);
+    assert_eq!(run(&resolved), run(&unresolved), "for:\n{}", source);
+}
+
+#[test]
+fn undeclared_variables() {
+    assert_eq!(diagnostics("let a = b + 1;"), vec!["1:9: variable `b` used without declaration"]);
+    assert_eq!(diagnostics("c = 2;"), vec!["1:1: variable `c` used without declaration"]);
+    assert_eq!(
+        diagnostics("{ let x = 1; } let y = x;"),
+        vec!["1:7: variable `x` is never used", "1:24: variable `x` used without declaration"]
+    );
+    assert_eq!(diagnostics("let f = fn() { g() };"), vec!["1:16: variable `g` used without declaration"]);
+    assert!(diagnostics("let n = len([1]); let f = fn() { n + push };").is_empty());
+}
+
+#[test]
+fn use_before_declaration() {
+    assert_eq!(diagnostics("let a = b; let b = 1;"), vec!["1:9: variable `b` used before its declaration"]);
+    assert_eq!(diagnostics("{ b += 1; } let mut b = 1;"), vec!["1:3: variable `b` used before its declaration"]);
+    // A function body runs later, by when the global may well be declared.
+    assert!(diagnostics("let f = fn() { g() }; let g = fn() { 1 }; let r = f();").is_empty());
+    assert!(diagnostics("let fact = fn(n) { if n < 2 { 1 } else { n * fact(n - 1) } };").is_empty());
+}
+
+#[test]
+fn duplicate_declarations() {
+    assert_eq!(
+        diagnostics("let a = 1;\nlet a = 2;"),
+        vec!["2:5: `a` is already declared in this scope, at 1:5"]
+    );
+    assert_eq!(
+        diagnostics("let f = fn(x, x) { x };"),
+        vec!["1:12: variable `x` is never used", "1:15: `x` is already declared in this scope, at 1:12"]
+    );
+    assert!(diagnostics("let a = 1; { let a = 2; let b = a; b; }").is_empty());
+}
+
+#[test]
+fn unused_bindings() {
+    assert_eq!(diagnostics("{ let x = 1; }"), vec!["1:7: variable `x` is never used"]);
+    assert_eq!(diagnostics("let f = fn(a, b) { a };"), vec!["1:15: variable `b` is never used"]);
+    assert_eq!(diagnostics("for i in 0..3 { }"), vec!["1:5: variable `i` is never used"]);
+    assert_eq!(diagnostics("{ let mut x = 1; x = 2; }"), vec!["1:11: variable `x` is never used"]);
+    assert!(diagnostics("{ let mut x = 1; x += 2; }").is_empty());
+    assert!(diagnostics("for _i in 0..3 { } let unused_global = 1;").is_empty());
+}
+
+#[test]
+fn errors_and_warnings() {
+    let found = resolve(&mut program("{ let x = 1; } y;"));
+    assert_eq!(found.len(), 2);
+    assert!(matches!(&found[0].kind, DiagnosticKind::Unused(name) if **name == *"x") && !found[0].is_error());
+    assert!(matches!(&found[1].kind, DiagnosticKind::Undeclared(name) if **name == *"y") && found[1].is_error());
+}
+
+/// A variable read and where the resolver put it.
+type Read = (String, Option<(usize, usize)>);
+
+/// Every variable read in a program.
+#[derive(Default)]
+struct Reads(Vec<Read>);
+
+impl Visitor for Reads {
+    fn visit_identifier(&mut self, identifier: &Identifier) {
+        self.0.push((identifier.name.to_string(), identifier.resolved));
+    }
+}
+
+fn reads(program: &Program) -> Vec<Read> {
+    let mut reads = Reads::default();
+    reads.visit_program(program);
+    reads.0
+}
+
+#[test]
+fn locals_get_scope_and_slot() {
+    let mut resolved = program("let g = 1; let f = fn(a, b) { let c = a; { let d = b; c + d + g } };");
+    resolve(&mut resolved);
+    let expected = [("a", Some((0, 0))), ("b", Some((1, 1))), ("c", Some((1, 2))), ("d", Some((0, 0))), ("g", None)];
+    assert_eq!(reads(&resolved), expected.map(|(name, resolved)| (name.to_string(), resolved)));
+}
+
+#[test]
+fn resolved_programs_run_the_same() {
+    assert_resolved_runs_the_same("let a = 1 + 2; let b = { let a = 5; a * 2 }; let c = a;");
+    assert_resolved_runs_the_same("let mut total = 0; for i in 0..5 { let sq = i * i; total += sq; }");
+    assert_resolved_runs_the_same("
+        let counter = fn() {
+            let mut count = 0;
+            fn() { count += 1; count }
+        };
+        let c = counter();
+        c(); c();
+        let r = c();
+    ");
+    assert_resolved_runs_the_same("
+        let f = fn() {
+            let go = fn(n) { if n == 0 { 0 } else { 1 + go(n - 1) } };
+            go(5)
+        };
+        let r = f();
+    ");
+    assert_resolved_runs_the_same("let r = { let fs = []; for i in 0..3 { push(fs, fn() { i }); } [fs[0](), fs[2]()] };");
+    assert_resolved_runs_the_same("let r = { let x = 1; let g = fn() { x }; let x = 2; g() };");
+    assert_resolved_runs_the_same("let r = { let x = 1; { let g = fn() { x }; let x = 7; g() } };");
+    assert_resolved_runs_the_same("let f = fn(a, a) { a }; let r = f(1, 2);");
+    assert_resolved_runs_the_same("let f = fn() { let x = 1; x = 2; }; f();");
+    assert_resolved_runs_the_same("let xs = [1, 2]; let mut seen = []; for x in xs { if len(xs) < 4 { push(xs, x * 10); } push(seen, x); }");
+    assert_resolved_runs_the_same("let mut i = 0; while i < 4 { let j = i; i += j + 1; }");
+}
+
+#[test]
+fn functions_cannot_use_later_locals() {
+    let source = "let r = { let f = fn() { g() }; let g = fn() { 1 }; f() };";
+    assert_eq!(
+        diagnostics(source),
+        vec!["1:26: variable `g` used without declaration", "1:37: variable `g` is never used"]
+    );
+    assert_eq!(run(&program(source)), "[] Some(\"1:26: variable `g` used without declaration\")");
+}
+
+#[test]
+fn resolving_twice_gives_the_same_tree() {
+    let mut once = program("let f = fn(a) { let b = a; fn() { a + b } };");
+    resolve(&mut once);
+    let mut twice = once.clone();
+    resolve(&mut twice);
+    assert_eq!(reads(&twice), reads(&once));
+}
+
+#[test]
+fn the_interpreter_resolves_what_it_runs() {
+    // Looked up by name, `x` would be the 7 declared after `g`.
+    assert_eq!(eval("{ let x = 1; { let g = fn() { x }; let x = 7; g() } }"), int(1));
+    assert_eq!(eval("{ let f = fn() { g() }; let g = fn() { 1 }; f() }"), error("1:31: variable `g` used without declaration"));
+}
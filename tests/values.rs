@@ -0,0 +1,42 @@
+mod common;
+
+use scriptx::datatypes::datatypes::DataType;
+use scriptx::interpreter::interpreter::Interpreter;
+use common::program;
+
+/// Runs `source` and returns the value it bound to `r`.
+fn run(source: &str) -> DataType {
+    let mut interpreter = Interpreter::new();
+    interpreter.run(&program(source)).expect("run failed");
+    interpreter.get("r").expect("`r` is not bound")
+}
+
+fn boolean(value: bool) -> DataType {
+    DataType::Boolean(value)
+}
+
+#[test]
+fn cyclic_values_compare_without_recursing_forever() {
+    assert_eq!(run("let xs = []; push(xs, xs); let r = xs == xs;"), boolean(true));
+    assert_eq!(run("let xs = []; push(xs, xs); let ys = []; push(ys, ys); let r = xs == ys;"), boolean(true));
+    assert_eq!(run("let xs = [1]; push(xs, xs); let ys = [2]; push(ys, ys); let r = xs == ys;"), boolean(false));
+    assert_eq!(run("let m = {}; m[\"self\"] = m; let r = m == m;"), boolean(true));
+    assert_eq!(run("let xs = []; push(xs, xs); let r = contains(xs, xs);"), boolean(true));
+}
+
+#[test]
+fn cyclic_values_print_as_ellipses() {
+    assert_eq!(
+        format!("{:?}", run("let r = [1]; push(r, r);")),
+        "List(RefCell { value: [Number(Integer(1)), [...]] })"
+    );
+    assert_eq!(
+        format!("{:?}", run("let r = {}; r[\"self\"] = r;")),
+        "Map(RefCell { value: {\"self\": {...}} })"
+    );
+    // A list that only appears twice, without containing itself, is printed in full.
+    assert_eq!(
+        format!("{:?}", run("let xs = [1]; let r = [xs, xs];")),
+        "List(RefCell { value: [List(RefCell { value: [Number(Integer(1))] }), List(RefCell { value: [Number(Integer(1))] })] })"
+    );
+}
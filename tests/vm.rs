@@ -0,0 +1,203 @@
+mod common;
+
+use std::rc::Rc;
+use scriptx::compiler::compiler::compile;
+use scriptx::datatypes::datatypes::DataType;
+use scriptx::interpreter::interpreter::Interpreter;
+use scriptx::vm::vm::Vm;
+use common::program;
+
+/// The bindings a run left behind and the error it stopped with, printed so
+/// the two engines can be compared. Function values are shown by name only,
+/// since each engine has its own kind.
+type Outcome = (Vec<(Rc<str>, String)>, Option<String>);
+
+fn describe(value: &DataType) -> String {
+    match value {
+        DataType::Function(function) => format!("{:?}", function),
+        DataType::Closure(closure) => format!("{:?}", closure),
+        other => format!("{:?}", other),
+    }
+}
+
+fn outcome(bindings: Vec<(Rc<str>, DataType)>, error: Option<String>) -> Outcome {
+    (bindings.into_iter().map(|(name, value)| (name, describe(&value))).collect(), error)
+}
+
+fn interpret(source: &str) -> Outcome {
+    let mut interpreter = Interpreter::new();
+    let error = interpreter.run(&program(source)).err().map(|e| e.to_string());
+    outcome(interpreter.bindings(), error)
+}
+
+fn execute(source: &str) -> Outcome {
+    let mut vm = Vm::new();
+    let error = vm.run(&Rc::new(compile(&program(source)))).err().map(|e| e.to_string());
+    outcome(vm.bindings(), error)
+}
+
+fn assert_same(source: &str) {
+    assert_eq!(execute(source), interpret(source), "for:\n{}", source);
+}
+
+#[test]
+fn arithmetic_and_comparisons() {
+    assert_same("let a = 1 + 2 * 3 - 4 / 2; let b = 7 % 3; let c = 2.5 * 2; let d = a < b; let e = a == 5 && !d;");
+    assert_same("let s = \"ab\" + \"cd\"; let t = -a;");
+    assert_same("let a = 9223372036854775807 + 1;");
+    assert_same("let a = 1 / 0;");
+}
+
+#[test]
+fn short_circuiting_operators() {
+    assert_same("let mut n = 0; let f = fn() { n += 1; true }; let a = true || f(); let b = false && f(); let c = false || f();");
+    assert_same("let a = 1 || true;");
+    assert_same("let a = false || 1;");
+    assert_same("let a = true && \"x\";");
+}
+
+#[test]
+fn blocks_and_if() {
+    assert_same("let a = { let x = 2; x * 3 }; let b = if a > 5 { \"big\" } else { \"small\" }; let c = if false { 1 };");
+    assert_same("let a = if 1 { 2 } else { 3 };");
+    assert_same("let a = if false { 1 } else if true { 2 } else { 3 };");
+    assert_same("let x = 1; { let x = 2; } let y = x;");
+}
+
+#[test]
+fn mutation_and_immutability() {
+    assert_same("let mut a = 1; a = 2; a += 3; a *= 2;");
+    assert_same("let a = 1; a = 2;");
+    assert_same("b = 2;");
+    assert_same("len = 2;");
+    assert_same("let f = fn() { let x = 1; x = 2; }; f();");
+    assert_same("let f = fn() { let mut x = 1; x -= 2; x }; let r = f();");
+    assert_same("let f = fn() { y }; let r = f();");
+    assert_same("let mut a = 1; a += \"x\";");
+}
+
+#[test]
+fn functions_and_closures() {
+    assert_same("let add = fn(a, b) { a + b }; let r = add(2, 3);");
+    assert_same("let fact = fn(n) { if n < 2 { return 1; } n * fact(n - 1) }; let r = fact(10);");
+    assert_same("
+        let counter = fn() {
+            let mut count = 0;
+            fn() { count += 1; count }
+        };
+        let c = counter();
+        c(); c();
+        let r = c();
+        let other = counter()();
+    ");
+    assert_same("
+        let outer = fn() {
+            let mut x = 1;
+            let middle = fn() { fn() { x = x * 10; x } };
+            let inner = middle();
+            inner();
+            x + inner()
+        };
+        let r = outer();
+    ");
+    assert_same("
+        let f = fn() {
+            let go = fn(n) { if n == 0 { 0 } else { 1 + go(n - 1) } };
+            go(5)
+        };
+        let r = f();
+    ");
+    assert_same("let f = fn(a, a) { a }; let r = f(1, 2);");
+    assert_same("let f = fn() { let x = 1; let g = fn() { x }; let x = 2; g() }; let r = f();");
+    assert_same("let f = fn(a) { a }; let r = f(1, 2);");
+    assert_same("let r = 5(1);");
+    assert_same("let f = fn() { return; }; let r = f(); let len = 3;");
+}
+
+#[test]
+fn functions_only_see_locals_declared_before_them() {
+    assert_same("let r = { let x = 1; { let g = fn() { x }; let x = 7; g() } };");
+    assert_same("let r = { let f = fn() { g() }; let g = fn() { 1 }; f() };");
+    assert_same("let x = 5; let r = { let g = fn() { x }; let x = 7; g() };");
+    assert_same("let r = { let mut x = 1; { let g = fn() { x = 2; }; let mut x = 7; g(); x } };");
+    assert_same("let r = { let g = fn() { fn() { y } }; let y = 1; g()() };");
+    assert_same("let f = fn() { g() }; let g = fn() { 1 }; let r = f();");
+}
+
+#[test]
+fn loops() {
+    assert_same("let mut i = 0; let mut total = 0; while i < 10 { i += 1; if i % 2 == 0 { continue; } total += i; }");
+    assert_same("let mut total = 0; for i in 0..5 { total += i; } for x in [1, 2, 3] { total *= x; }");
+    assert_same("
+        let mut found = [];
+        outer: for i in 0..5 {
+            for j in 0..5 {
+                if j > i { continue outer; }
+                if i == 4 { break outer; }
+                push(found, [i, j]);
+            }
+        }
+    ");
+    assert_same("let r = { let fs = []; for i in 0..3 { push(fs, fn() { i }); } [fs[0](), fs[2]()] };");
+    assert_same("let r = { let fs = []; let mut i = 0; while i < 3 { let j = i; push(fs, fn() { j }); i += 1; } fs[0]() };");
+    assert_same("let xs = [1, 2]; let mut seen = []; for x in xs { if len(xs) < 4 { push(xs, x * 10); } push(seen, x); }");
+    assert_same("let m = {\"a\": 1, \"b\": 2}; let mut ks = []; for k in m { insert(m, k + k, 0); push(ks, k); }");
+    assert_same("let m = {\"a\": 1, \"b\": 2, \"c\": 3}; let ks = []; for k in m { if k == \"a\" { remove(m, \"b\"); } push(ks, [k, m[k]]); }");
+    assert_same("let mut r = 0; for x in [1, 2, 3] { r = r + [10, { if x == 2 { break; } x }][1]; }");
+    assert_same("for i in 0..\"a\" { }");
+    assert_same("for i in 5 { }");
+    assert_same("while 1 { }");
+    assert_same("break;");
+    assert_same("let f = fn() { continue outer; }; while true { f(); }");
+    assert_same("return 1;");
+    assert_same("for i in 0..3 { i = 2; }");
+}
+
+#[test]
+fn lists_and_maps() {
+    assert_same("let xs = [1, 2, 3]; xs[0] = 10; xs[1] += 5; let a = xs[-1]; let b = xs[1..]; let c = xs[..1]; let d = len(xs);");
+    assert_same("let xs = [1]; xs[5] = 1;");
+    assert_same("let xs = [1]; xs[0] += \"x\";");
+    assert_same("let m = {\"a\": 1, \"b\": {\"c\": 2}}; m.a = 5; m.b.c *= 10; m[\"d\"] = [1]; let v = m.b.c;");
+    assert_same("let m = {}; let v = m.missing;");
+    assert_same("let m = {\"a\": 1, \"a\": 2}; let s = \"hello\"[1..3];");
+    assert_same("let m = {\"x\": 1}; m.y += 1;");
+}
+
+#[test]
+fn runs_share_globals() {
+    let mut vm = Vm::new();
+    vm.run(&Rc::new(compile(&program("let mut a = 1; let f = fn() { a * 2 };")))).unwrap();
+    vm.run(&Rc::new(compile(&program("a = 5; let b = f();")))).unwrap();
+    assert_eq!(format!("{:?}", vm.get("b").unwrap()), "Number(Integer(10))");
+}
+
+#[test]
+fn runs_do_not_keep_finished_bytecode_alive() {
+    let mut vm = Vm::new();
+    let kept = Rc::new(compile(&program("let f = fn() { 1 };")));
+    vm.run(&kept).unwrap();
+    let kept = Rc::downgrade(&kept);
+
+    let mut dropped = vec![];
+    for i in 0..100 {
+        let bytecode = Rc::new(compile(&program(&format!("let x{} = f() + {};", i, i))));
+        vm.run(&bytecode).unwrap();
+        dropped.push(Rc::downgrade(&bytecode));
+    }
+
+    assert!(dropped.iter().all(|bytecode| bytecode.upgrade().is_none()));
+    // `f` still refers to the first program.
+    assert!(kept.upgrade().is_some());
+    assert_eq!(format!("{:?}", vm.get("x99").unwrap()), "Number(Integer(100))");
+}
+
+#[test]
+fn map_loops_skip_keys_removed_by_the_body() {
+    let source = "let m = {\"a\": 1, \"b\": 2, \"c\": 3}; let ks = []; for k in m { if k == \"a\" { remove(m, \"b\"); } push(ks, k); } let r = ks;";
+    let expected = r#"List(RefCell { value: [String("a"), String("c")] })"#;
+    let (bindings, error) = interpret(source);
+    assert_eq!(error, None);
+    assert_eq!(bindings.iter().find(|(name, _)| &**name == "r").map(|(_, value)| value.as_str()), Some(expected));
+    assert_eq!(execute(source), interpret(source));
+}